@@ -1,7 +1,5 @@
-use std::{fs::File, path::Path};
-
 use libeh::{
-    client::{client::EhClient, config::EhClientConfig},
+    client::{client::EhClient, config::EhClientConfig, loader::EhConfigLoader},
     dto::{keyword::Keyword, search_result::SearchResult},
 };
 
@@ -9,7 +7,7 @@ use libeh::{
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let config = open_config().await?;
     println!("Config: {:?}", config);
-    let client = EhClient::new(config);
+    let client = EhClient::try_new(config)?;
     let res = client
         .search(
             vec![
@@ -28,14 +26,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 }
 
 async fn open_config() -> Result<EhClientConfig, Box<dyn std::error::Error>> {
-    let config_path = Path::new("config.yaml");
-    let config = if config_path.exists() {
-        let file = File::open(config_path)?;
-        let conf: EhClientConfig = serde_yaml::from_reader(file)?;
-        conf
-    } else {
-        let conf = EhClientConfig::default();
-        conf
-    };
-    Ok(config)
+    let loaded = EhConfigLoader::new()
+        .optional_file("config.toml")?
+        .optional_file("config.yaml")?
+        .optional_file("config.json")?
+        .env()?
+        .load()?;
+    Ok(loaded.config)
 }
@@ -0,0 +1,309 @@
+use std::{
+    collections::BTreeMap,
+    ops::Deref,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{EhError, EhResult};
+
+use super::{
+    auth::EhClientAuth, client::EhClient, config::EhClientConfig, cookie::is_valid_account_name,
+    limiter::EhRateLimitConfig, proxy::EhClientProxy,
+};
+
+/// 单个账号的设置，未设置的项使用注册表的基础配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EhAccountConfig {
+    /// 账号名称，在注册表中唯一，同时用作 Cookie 文件名与缓存目录名
+    pub name: String,
+    /// 账号的身份验证信息
+    pub auth: EhClientAuth,
+    /// 账号使用的代理，默认为 None，即使用基础配置的代理
+    #[serde(default)]
+    pub proxy: Option<EhClientProxy>,
+    /// 账号的请求频率限制，默认为 None，即使用基础配置的限制
+    #[serde(default)]
+    pub rate_limit: Option<EhRateLimitConfig>,
+}
+
+impl EhAccountConfig {
+    /// 创建一个新的 EhAccountConfig 实例
+    pub fn new(name: &str, auth: EhClientAuth) -> Self {
+        EhAccountConfig {
+            name: name.to_string(),
+            auth,
+            proxy: None,
+            rate_limit: None,
+        }
+    }
+
+    /// 设置账号使用的代理
+    pub fn proxy(mut self, proxy: EhClientProxy) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// 设置账号的请求频率限制
+    pub fn rate_limit(mut self, rate_limit: EhRateLimitConfig) -> Self {
+        self.rate_limit = Some(rate_limit);
+        self
+    }
+
+    /// 在基础配置上应用账号设置
+    fn apply(&self, base: &EhClientConfig) -> EhClientConfig {
+        let mut config = base.clone();
+        config.auth = Some(self.auth.clone());
+        // 账号的身份验证信息取代基础配置中的凭据文件
+        config.credentials = None;
+        if let Some(proxy) = &self.proxy {
+            config.proxy = Some(proxy.clone());
+            config.proxy_pool = None;
+        }
+        if let Some(rate_limit) = &self.rate_limit {
+            config.rate_limit = Some(rate_limit.clone());
+        }
+        if let Some(store) = &mut config.cookie_store {
+            store.account = Some(self.name.clone());
+        }
+        if let Some(cache) = &mut config.cache {
+            cache.dir = cache.dir.join(&self.name);
+        }
+        config
+    }
+}
+
+/// 注册表中的账号
+struct Account {
+    client: EhClient,
+    /// 正在使用该账号的租约数
+    in_flight: AtomicUsize,
+    /// 累计发出的租约数
+    leased: AtomicUsize,
+}
+
+/// 账号的租约，可作为 [`EhClient`] 使用，释放时减少账号的负载计数
+pub struct EhAccountLease {
+    name: String,
+    account: Arc<Account>,
+}
+
+impl EhAccountLease {
+    /// 账号名称
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl Deref for EhAccountLease {
+    type Target = EhClient;
+
+    fn deref(&self) -> &EhClient {
+        &self.account.client
+    }
+}
+
+impl Drop for EhAccountLease {
+    fn drop(&mut self) {
+        self.account.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// 账号注册表，每个账号拥有独立的 Cookie、频率限制与代理
+///
+/// 可以按名称使用指定账号，或由注册表选择当前负载最低的账号。负载为尚未释放的
+/// [`EhAccountLease`] 数量，相同时选择累计使用次数较少的账号。
+#[derive(Default)]
+pub struct EhAccountRegistry {
+    accounts: BTreeMap<String, Arc<Account>>,
+}
+
+impl EhAccountRegistry {
+    /// 创建一个空的注册表
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 以基础配置为每个账号创建客户端，账号名称重复或配置无效时返回错误
+    ///
+    /// 基础配置启用了 Cookie 持久化时，每个账号使用以账号名称命名的 Cookie 文件；
+    /// 启用了缓存时，每个账号使用缓存目录下以账号名称命名的子目录。
+    pub fn from_config(base: &EhClientConfig, accounts: &[EhAccountConfig]) -> EhResult<Self> {
+        accounts.iter().try_fold(Self::new(), |registry, account| {
+            if !is_valid_account_name(&account.name) {
+                return Err(EhError::invalid_input(format!(
+                    "Invalid account name: {}",
+                    account.name
+                )));
+            }
+            let client = EhClient::try_new(account.apply(base))?;
+            registry.add(&account.name, client)
+        })
+    }
+
+    /// 添加一个使用指定客户端的账号，名称重复时返回错误
+    pub fn add(mut self, name: &str, client: EhClient) -> EhResult<Self> {
+        if self.accounts.contains_key(name) {
+            return Err(EhError::invalid_input(format!(
+                "Duplicate account name: {}",
+                name
+            )));
+        }
+        let account = Account {
+            client,
+            in_flight: AtomicUsize::new(0),
+            leased: AtomicUsize::new(0),
+        };
+        self.accounts.insert(name.to_string(), Arc::new(account));
+        Ok(self)
+    }
+
+    /// 所有账号的名称
+    pub fn names(&self) -> Vec<&str> {
+        self.accounts.keys().map(String::as_str).collect()
+    }
+
+    /// 账号的当前负载，账号不存在时返回 None
+    pub fn load(&self, name: &str) -> Option<usize> {
+        self.accounts
+            .get(name)
+            .map(|account| account.in_flight.load(Ordering::Relaxed))
+    }
+
+    /// 使用指定账号，账号不存在时返回错误
+    pub fn account(&self, name: &str) -> EhResult<EhAccountLease> {
+        let account = self
+            .accounts
+            .get(name)
+            .ok_or_else(|| EhError::invalid_input(format!("Unknown account: {}", name)))?;
+        Ok(Self::lease(name, account))
+    }
+
+    /// 使用当前负载最低的账号，注册表为空时返回错误
+    pub fn least_loaded(&self) -> EhResult<EhAccountLease> {
+        self.accounts
+            .iter()
+            .min_by_key(|(_, account)| {
+                (
+                    account.in_flight.load(Ordering::Relaxed),
+                    account.leased.load(Ordering::Relaxed),
+                )
+            })
+            .map(|(name, account)| Self::lease(name, account))
+            .ok_or_else(|| EhError::invalid_input("No account in registry."))
+    }
+
+    fn lease(name: &str, account: &Arc<Account>) -> EhAccountLease {
+        account.in_flight.fetch_add(1, Ordering::Relaxed);
+        account.leased.fetch_add(1, Ordering::Relaxed);
+        EhAccountLease {
+            name: name.to_string(),
+            account: account.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::Url;
+
+    use crate::client::{
+        auth::EhClientAuth,
+        cache::EhCacheConfig,
+        config::EhClientConfig,
+        credentials::{EhCredentialKey, EhCredentialsConfig},
+        test::{TestResponse, TestServer},
+    };
+
+    use super::{EhAccountConfig, EhAccountRegistry};
+
+    #[test]
+    fn test_account_registry() {
+        let accounts = [
+            EhAccountConfig::new("alice", EhClientAuth::new("1", "hash-a", None)),
+            EhAccountConfig::new("bob", EhClientAuth::new("2", "hash-b", None)),
+        ];
+        let registry =
+            EhAccountRegistry::from_config(&EhClientConfig::default(), &accounts).unwrap();
+        assert_eq!(registry.names(), vec!["alice", "bob"]);
+
+        let url = Url::parse("https://e-hentai.org/").unwrap();
+        let bob = registry.account("bob").unwrap();
+        assert!(bob
+            .cookies(&url)
+            .contains(&("ipb_member_id".to_string(), "2".to_string())));
+        assert_eq!(registry.load("bob"), Some(1));
+
+        let first = registry.least_loaded().unwrap();
+        assert_eq!(first.name(), "alice");
+        let second = registry.least_loaded().unwrap();
+        assert_eq!(second.name(), "alice");
+        drop(bob);
+        assert_eq!(registry.least_loaded().unwrap().name(), "bob");
+        drop(first);
+        drop(second);
+        assert_eq!(registry.load("alice"), Some(0));
+
+        assert!(registry.account("carol").is_err());
+
+        // 账号的身份验证信息取代基础配置中的凭据文件，不会读取凭据文件
+        let base = EhClientConfig {
+            credentials: Some(EhCredentialsConfig::new(
+                "missing-credentials.json",
+                EhCredentialKey::Passphrase("passphrase".into()),
+            )),
+            ..Default::default()
+        };
+        let registry = EhAccountRegistry::from_config(&base, &accounts).unwrap();
+        let alice = registry.account("alice").unwrap();
+        assert!(alice
+            .cookies(&url)
+            .contains(&("ipb_member_id".to_string(), "1".to_string())));
+        assert!(EhAccountRegistry::from_config(
+            &EhClientConfig::default(),
+            &[accounts[0].clone(), accounts[0].clone()]
+        )
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_account_cache() {
+        let server = TestServer::start(vec![(
+            "/",
+            vec![
+                TestResponse::new(200, "alice"),
+                TestResponse::new(200, "bob"),
+            ],
+        )])
+        .await;
+        let dir = std::env::temp_dir().join(format!("libeh-accounts-{}", std::process::id()));
+        let base = EhClientConfig {
+            cache: Some(EhCacheConfig::new(&dir)),
+            ..Default::default()
+        };
+        let accounts = [
+            EhAccountConfig::new("alice", EhClientAuth::new("1", "hash-a", None)),
+            EhAccountConfig::new("bob", EhClientAuth::new("2", "hash-b", None)),
+        ];
+        let registry = EhAccountRegistry::from_config(&base, &accounts).unwrap();
+        let url = server.url("/favorites.php");
+        let alice = registry.account("alice").unwrap();
+        let bob = registry.account("bob").unwrap();
+        assert_eq!(alice.get_html(url.clone()).await.unwrap(), "alice");
+        assert_eq!(bob.get_html(url.clone()).await.unwrap(), "bob");
+        assert_eq!(alice.get_html(url).await.unwrap(), "alice");
+        assert_eq!(server.requests().len(), 2);
+        assert!(dir.join("alice").is_dir() && dir.join("bob").is_dir());
+
+        let invalid = [EhAccountConfig::new(
+            "../x",
+            EhClientAuth::new("3", "hash-c", None),
+        )];
+        assert!(EhAccountRegistry::from_config(&base, &invalid).is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
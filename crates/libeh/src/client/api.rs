@@ -0,0 +1,382 @@
+use std::{collections::HashMap, future::Future};
+
+use futures::{stream, StreamExt};
+use reqwest::Url;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    dto::api::{
+        GIDListItem, GalleryMetadataError, GalleryMetadataRequest, GalleryMetadataResponse,
+        GalleryMetadataResult, GalleryTokenError, GalleryTokenResponse, GalleryTokenResult,
+        GalleryTokensRequest, PageListItem,
+    },
+    error::{EhError, EhResult},
+};
+
+use super::{cancel::EhOutcome, client::EhClient};
+
+/// 同时进行的 API 请求数，实际频率仍受客户端的频率限制约束
+const API_CONCURRENCY: usize = 4;
+
+impl EhClient {
+    /// 批量获取画廊元数据，结果与 `items` 一一对应
+    ///
+    /// 每 25 个画廊合并为一个 API 请求，多个请求并发进行。API 返回的错误条目与失败请求中的画廊
+    /// 以 [`GalleryMetadataError`] 表示，不影响其他画廊。客户端绑定的取消令牌被取消时，
+    /// 返回 [`EhOutcome::Cancelled`] 与从第一项起连续完成的结果，即 `items` 前若干项的结果，
+    /// 之后的画廊即使所在请求已完成也不包含在内。
+    pub async fn gallery_metadata(
+        &self,
+        items: Vec<GIDListItem>,
+    ) -> EhResult<EhOutcome<Vec<GalleryMetadataResult>>> {
+        let url = self.endpoints.api()?;
+        let outcome = self
+            .batched(items, GalleryMetadataRequest::MAX_GALLERIES, |chunk| {
+                self.gallery_metadata_chunk(url.clone(), chunk)
+            })
+            .await;
+        Ok(outcome)
+    }
+
+    /// 通过页面反查画廊令牌，结果与 `pages` 一一对应
+    ///
+    /// `pages` 可以是页面 URL（`/s/{page_token}/{gid}-{page}`）或 `(gid, page_token, page)`，
+    /// 存在无法识别的 URL 时返回错误。分组、并发与取消的处理与
+    /// [`gallery_metadata`](EhClient::gallery_metadata) 相同。
+    pub async fn gallery_tokens<P>(
+        &self,
+        pages: impl IntoIterator<Item = P>,
+    ) -> EhResult<EhOutcome<Vec<GalleryTokenResult>>>
+    where
+        P: TryInto<PageListItem>,
+        EhError: From<P::Error>,
+    {
+        let pages = pages
+            .into_iter()
+            .map(|page| Ok(page.try_into()?))
+            .collect::<EhResult<Vec<PageListItem>>>()?;
+        let url = self.endpoints.api()?;
+        let outcome = self
+            .batched(pages, GalleryTokensRequest::MAX_PAGES, |chunk| {
+                self.gallery_tokens_chunk(url.clone(), chunk)
+            })
+            .await;
+        Ok(outcome)
+    }
+
+    /// 将 `items` 按 `size` 分组并发请求，按原顺序合并结果
+    ///
+    /// 有组被取消时只保留第一个被取消的组之前的结果，使结果始终与 `items` 的前若干项一一对应。
+    async fn batched<T, R, F, Fut>(
+        &self,
+        items: Vec<T>,
+        size: usize,
+        request: F,
+    ) -> EhOutcome<Vec<R>>
+    where
+        T: Clone,
+        F: Fn(Vec<T>) -> Fut,
+        Fut: Future<Output = Option<Vec<R>>>,
+    {
+        let chunks: Vec<Vec<T>> = items.chunks(size).map(<[T]>::to_vec).collect();
+        let results: Vec<_> = stream::iter(chunks)
+            .map(request)
+            .buffered(API_CONCURRENCY)
+            .collect()
+            .await;
+        let cancelled = results.iter().any(Option::is_none);
+        let results = results.into_iter().map_while(|r| r).flatten().collect();
+        match cancelled {
+            true => EhOutcome::Cancelled(results),
+            false => EhOutcome::from_token(self.cancel_token(), results),
+        }
+    }
+
+    /// 提交 API 请求，请求被取消时返回 None
+    async fn api_request<T, R>(&self, url: Url, body: &T) -> Option<EhResult<R>>
+    where
+        T: Serialize,
+        R: DeserializeOwned,
+    {
+        let result = match serde_json::to_string(body) {
+            Ok(body) => self.post_json(url, body).await,
+            Err(err) => Err(EhError::invalid_input(err.to_string())),
+        };
+        match result {
+            Err(EhError::Cancelled) => None,
+            result => Some(result),
+        }
+    }
+
+    /// 请求一组画廊的元数据，请求被取消时返回 None
+    async fn gallery_metadata_chunk(
+        &self,
+        url: Url,
+        chunk: Vec<GIDListItem>,
+    ) -> Option<Vec<GalleryMetadataResult>> {
+        let body = GalleryMetadataRequest::new(chunk.clone());
+        let response: GalleryMetadataResponse = match self.api_request(url, &body).await? {
+            Ok(response) => response,
+            Err(err) => {
+                let error = err.to_string();
+                let results = chunk
+                    .iter()
+                    .map(|item| {
+                        Err(GalleryMetadataError {
+                            gid: item.gid(),
+                            error: error.clone(),
+                        })
+                    })
+                    .collect();
+                return Some(results);
+            }
+        };
+        // 同一画廊可能在一组中出现多次
+        let metadata: HashMap<i64, _> = response
+            .gmetadata
+            .into_iter()
+            .map(|metadata| (metadata.gid, metadata))
+            .collect();
+        let errors: HashMap<i64, _> = response
+            .errors
+            .into_iter()
+            .map(|err| (err.gid, err))
+            .collect();
+        let results =
+            chunk
+                .iter()
+                .map(|item| match metadata.get(&item.gid()) {
+                    Some(metadata) => Ok(metadata.clone()),
+                    None => Err(errors.get(&item.gid()).cloned().unwrap_or_else(|| {
+                        GalleryMetadataError {
+                            gid: item.gid(),
+                            error: "Gallery missing from response.".to_string(),
+                        }
+                    })),
+                })
+                .collect();
+        Some(results)
+    }
+
+    /// 反查一组页面的画廊令牌，请求被取消时返回 None
+    async fn gallery_tokens_chunk(
+        &self,
+        url: Url,
+        chunk: Vec<PageListItem>,
+    ) -> Option<Vec<GalleryTokenResult>> {
+        let body = GalleryTokensRequest::new(chunk.clone());
+        let response: GalleryTokenResponse = match self.api_request(url, &body).await? {
+            Ok(response) => response,
+            Err(err) => {
+                let error = err.to_string();
+                let results = chunk
+                    .iter()
+                    .map(|page| {
+                        Err(GalleryTokenError {
+                            gid: page.gid(),
+                            error: error.clone(),
+                        })
+                    })
+                    .collect();
+                return Some(results);
+            }
+        };
+        // 同一画廊的多个页面对应同一个令牌
+        let tokens: HashMap<i64, _> = response
+            .tokenlist
+            .into_iter()
+            .map(|item| (item.gid, item.token))
+            .collect();
+        let errors: HashMap<i64, _> = response
+            .errors
+            .into_iter()
+            .map(|err| (err.gid, err))
+            .collect();
+        let results = chunk
+            .iter()
+            .map(|page| match tokens.get(&page.gid()) {
+                Some(token) => Ok(GIDListItem::new(page.gid(), token)),
+                None => {
+                    Err(errors
+                        .get(&page.gid())
+                        .cloned()
+                        .unwrap_or_else(|| GalleryTokenError {
+                            gid: page.gid(),
+                            error: "Page missing from response.".to_string(),
+                        }))
+                }
+            })
+            .collect();
+        Some(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use async_trait::async_trait;
+    use reqwest::Request;
+
+    use crate::{
+        client::{
+            cancel::{EhCancelToken, EhOutcome},
+            client::EhClient,
+            config::EhClientConfig,
+            transport::{EhResponse, EhTransport},
+        },
+        dto::api::{GIDListItem, PageListItem},
+        error::{EhError, EhResult},
+    };
+
+    /// 按请求生成 API 响应的传输层，gid 为 3 的画廊返回错误
+    #[derive(Default)]
+    struct ApiTransport {
+        batches: Mutex<Vec<usize>>,
+    }
+
+    impl ApiTransport {
+        fn metadata(item: &serde_json::Value) -> serde_json::Value {
+            serde_json::json!({
+                "gid": item[0], "token": item[1], "archiver_key": null,
+                "title": "title", "title_jpn": "", "category": "Manga",
+                "thumb": "", "uploader": "uploader", "posted": "1700000000",
+                "filecount": "20", "filesize": 1024, "expunged": false,
+                "rating": "4.5", "torrentcount": "0", "torrents": [],
+                "tags": ["language:chinese"], "parent_gid": null, "parent_key": null,
+                "first_gid": null, "first_key": null
+            })
+        }
+    }
+
+    #[async_trait]
+    impl EhTransport for ApiTransport {
+        async fn execute(&self, request: Request) -> EhResult<EhResponse> {
+            let body = request.body().and_then(|body| body.as_bytes()).unwrap();
+            let body: serde_json::Value = serde_json::from_slice(body).unwrap();
+            let (key, list) = match body["method"].as_str().unwrap() {
+                "gdata" => ("gmetadata", body["gidlist"].as_array().unwrap()),
+                _ => ("tokenlist", body["pagelist"].as_array().unwrap()),
+            };
+            self.batches.lock().unwrap().push(list.len());
+            let entries: Vec<_> = list
+                .iter()
+                .map(|item| {
+                    let gid = item[0].as_i64().unwrap();
+                    if gid == 3 {
+                        let error = "Key missing, or incorrect key provided.";
+                        return serde_json::json!({ "gid": gid, "error": error });
+                    }
+                    match key {
+                        "gmetadata" => Self::metadata(item),
+                        _ => serde_json::json!({ "gid": gid, "token": format!("{:010x}", gid) }),
+                    }
+                })
+                .collect();
+            let body = serde_json::json!({ key: entries }).to_string();
+            Ok(EhResponse::new(200, body))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_gallery_metadata_batches() {
+        let transport = Arc::new(ApiTransport::default());
+        let client = EhClient::new_with_transport(EhClientConfig::default(), transport.clone());
+        let items = (1..=30)
+            .map(|gid| GIDListItem::new(gid, "abcdef0123"))
+            .collect();
+        let outcome = client.gallery_metadata(items).await.unwrap();
+        assert!(!outcome.is_cancelled());
+        let results = outcome.into_inner();
+        assert_eq!(results.len(), 30);
+        assert_eq!(results[0].as_ref().unwrap().gid, 1);
+        assert_eq!(results[29].as_ref().unwrap().gid, 30);
+        let err = results[2].as_ref().unwrap_err();
+        assert_eq!(err.gid, 3);
+        assert_eq!(err.error, "Key missing, or incorrect key provided.");
+        let mut batches = transport.batches.lock().unwrap().clone();
+        batches.sort();
+        assert_eq!(batches, vec![5, 25]);
+    }
+
+    #[tokio::test]
+    async fn test_gallery_metadata_duplicates() {
+        let transport = Arc::new(ApiTransport::default());
+        let client = EhClient::new_with_transport(EhClientConfig::default(), transport.clone());
+        let items = vec![
+            GIDListItem::new(1, "abcdef0123"),
+            GIDListItem::new(3, "abcdef0123"),
+            GIDListItem::new(1, "abcdef0123"),
+            GIDListItem::new(3, "abcdef0123"),
+        ];
+        let results = client.gallery_metadata(items).await.unwrap().into_inner();
+        assert_eq!(results[0].as_ref().unwrap().gid, 1);
+        assert_eq!(results[2].as_ref().unwrap().gid, 1);
+        let error = "Key missing, or incorrect key provided.";
+        assert_eq!(results[1].as_ref().unwrap_err().error, error);
+        assert_eq!(results[3].as_ref().unwrap_err().error, error);
+    }
+
+    #[tokio::test]
+    async fn test_gallery_metadata_cancelled() {
+        let transport = Arc::new(ApiTransport::default());
+        let token = EhCancelToken::new();
+        token.cancel();
+        let client = EhClient::new_with_transport(EhClientConfig::default(), transport.clone())
+            .with_cancel(token);
+        let items = vec![GIDListItem::new(1, "abcdef0123")];
+        let outcome = client.gallery_metadata(items).await.unwrap();
+        assert!(matches!(outcome, EhOutcome::Cancelled(ref results) if results.is_empty()));
+        assert!(transport.batches.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_batched_cancelled_prefix() {
+        let client = EhClient::new_with_transport(
+            EhClientConfig::default(),
+            Arc::new(ApiTransport::default()),
+        );
+        let items: Vec<i64> = (0..7).collect();
+        // 第二组被取消，第三组虽已完成也不包含在结果中
+        let outcome = client
+            .batched(items, 3, |chunk| async move {
+                match chunk[0] {
+                    3 => None,
+                    _ => Some(chunk),
+                }
+            })
+            .await;
+        assert!(matches!(outcome, EhOutcome::Cancelled(ref results) if *results == vec![0, 1, 2]));
+    }
+
+    #[tokio::test]
+    async fn test_gallery_tokens() {
+        let transport = Arc::new(ApiTransport::default());
+        let client = EhClient::new_with_transport(EhClientConfig::default(), transport.clone());
+        let urls = [
+            "https://e-hentai.org/s/d384d63ec0/1-8",
+            "https://exhentai.org/s/0123456789/3-1",
+        ];
+        let results = client.gallery_tokens(urls).await.unwrap().into_inner();
+        assert_eq!(results[0], Ok(GIDListItem::new(1, "0000000001")));
+        assert_eq!(results[1].as_ref().unwrap_err().gid, 3);
+
+        let pages: Vec<_> = (1..=26).map(|gid| (gid, "d384d63ec0", 1)).collect();
+        let results = client.gallery_tokens(pages).await.unwrap().into_inner();
+        assert_eq!(results.len(), 26);
+        assert_eq!(results[25], Ok(GIDListItem::new(26, "000000001a")));
+
+        let pages = vec![PageListItem::new(2, "d384d63ec0", 5)];
+        let results = client.gallery_tokens(pages).await.unwrap().into_inner();
+        assert_eq!(results[0], Ok(GIDListItem::new(2, "0000000002")));
+
+        let invalid = ["https://e-hentai.org/g/1/abcdef0123/"];
+        assert!(matches!(
+            client.gallery_tokens(invalid).await,
+            Err(EhError::InvalidInput(_))
+        ));
+        let mut batches = transport.batches.lock().unwrap().clone();
+        batches.sort();
+        assert_eq!(batches, vec![1, 1, 2, 25]);
+    }
+}
@@ -1,9 +1,9 @@
-use std::env;
+use std::{env, fmt};
 
 use serde::{Deserialize, Serialize};
 
 /// E-Hentai/ExHentai 用户身份验证信息
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct EhClientAuth {
     /// E-Hentai/ExHentai 用户 ID
     pub ipb_member_id: String,
@@ -42,9 +42,10 @@ impl EhClientAuth {
 
     /// 输出验证信息为一个键值对向量
     pub fn to_vec(&self) -> Vec<(String, String)> {
-        let mut vec = Vec::new();
-        vec.push(("ipb_member_id".to_string(), self.ipb_member_id.to_string()));
-        vec.push(("ipb_pass_hash".to_string(), self.ipb_pass_hash.to_string()));
+        let mut vec = vec![
+            ("ipb_member_id".to_string(), self.ipb_member_id.to_string()),
+            ("ipb_pass_hash".to_string(), self.ipb_pass_hash.to_string()),
+        ];
         if let Some(igneous) = &self.igneous {
             vec.push(("igneous".to_string(), igneous.to_string()));
         }
@@ -52,17 +53,25 @@ impl EhClientAuth {
     }
 }
 
-impl ToString for EhClientAuth {
-    /// 将 EhClientAuth 实例转换为字符串
-    fn to_string(&self) -> String {
-        let mut s = format!(
-            "ipb_member_id={}&ipb_pass_hash={}",
-            self.ipb_member_id, self.ipb_pass_hash
-        );
-        if let Some(igneous) = &self.igneous {
-            s += &format!("&igneous={}", igneous);
+impl fmt::Display for EhClientAuth {
+    /// 将 EhClientAuth 实例转换为字符串，隐藏 ipb_pass_hash 与 igneous
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ipb_member_id={}&ipb_pass_hash=***", self.ipb_member_id)?;
+        if self.igneous.is_some() {
+            write!(f, "&igneous=***")?;
         }
-        s
+        Ok(())
+    }
+}
+
+impl fmt::Debug for EhClientAuth {
+    /// 隐藏 ipb_pass_hash 与 igneous，避免令牌出现在日志中
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EhClientAuth")
+            .field("ipb_member_id", &self.ipb_member_id)
+            .field("ipb_pass_hash", &"***")
+            .field("igneous", &self.igneous.as_ref().map(|_| "***"))
+            .finish()
     }
 }
 
@@ -75,7 +84,17 @@ mod tests {
         let auth = EhClientAuth::new("123456", "123456", Some("abcdef"));
         assert_eq!(
             auth.to_string(),
-            "ipb_member_id=123456&ipb_pass_hash=123456&igneous=abcdef"
+            "ipb_member_id=123456&ipb_pass_hash=***&igneous=***"
         );
+        let auth = EhClientAuth::new("123456", "123456", None);
+        assert_eq!(auth.to_string(), "ipb_member_id=123456&ipb_pass_hash=***");
+    }
+
+    #[test]
+    fn auth_debug_redacted() {
+        let auth = EhClientAuth::new("123456", "secret-hash", Some("secret-igneous"));
+        let debug = format!("{:?}", auth);
+        assert!(debug.contains("123456"));
+        assert!(!debug.contains("secret"));
     }
 }
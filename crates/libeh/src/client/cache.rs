@@ -0,0 +1,477 @@
+use std::{
+    collections::HashSet,
+    fs::{self, File},
+    io::{BufReader, BufWriter},
+    path::PathBuf,
+    sync::Mutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use reqwest::{Method, Request};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{EhError, EhResult};
+
+use super::endpoints::EhEndpoints;
+
+/// 缓存使用方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum EhCacheMode {
+    /// 优先使用未过期的缓存，否则请求网络并写入缓存
+    #[default]
+    #[serde(rename = "normal")]
+    Normal,
+    /// 不读取也不写入缓存
+    #[serde(rename = "bypass")]
+    Bypass,
+    /// 仅从缓存读取，不访问网络
+    #[serde(rename = "offline")]
+    Offline,
+}
+
+/// 缓存的资源类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EhCacheKind {
+    /// API 请求，如 gdata、gtoken
+    Api,
+    /// 画廊详情页面
+    Gallery,
+    /// 其他页面，如搜索结果
+    Page,
+}
+
+/// 各类资源的缓存有效期
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct EhCacheTtl {
+    /// API 响应的有效期，单位为秒
+    pub api_secs: u64,
+    /// 画廊详情页面的有效期，单位为秒
+    pub gallery_secs: u64,
+    /// 其他页面的有效期，单位为秒
+    pub page_secs: u64,
+}
+
+impl EhCacheTtl {
+    /// 获取资源类型对应的有效期
+    pub fn get(&self, kind: EhCacheKind) -> Duration {
+        Duration::from_secs(match kind {
+            EhCacheKind::Api => self.api_secs,
+            EhCacheKind::Gallery => self.gallery_secs,
+            EhCacheKind::Page => self.page_secs,
+        })
+    }
+}
+
+impl Default for EhCacheTtl {
+    /// 创建一个默认的有效期设置：API 与画廊 1 小时，其他页面 5 分钟
+    fn default() -> Self {
+        EhCacheTtl {
+            api_secs: 3600,
+            gallery_secs: 3600,
+            page_secs: 300,
+        }
+    }
+}
+
+/// 响应缓存设置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EhCacheConfig {
+    /// 存放缓存文件的目录
+    pub dir: PathBuf,
+    /// 各类资源的有效期
+    #[serde(default)]
+    pub ttl: EhCacheTtl,
+    /// 过期后仍可先返回旧响应、同时在后台刷新的时长，单位为秒
+    #[serde(default = "EhCacheConfig::default_stale_secs")]
+    pub stale_secs: u64,
+    /// 缓存目录的大小上限，单位为字节，超出时删除最早写入的缓存
+    #[serde(default = "EhCacheConfig::default_max_size")]
+    pub max_size: u64,
+    /// 是否启用离线模式，启用后只从缓存读取
+    #[serde(default)]
+    pub offline: bool,
+}
+
+impl EhCacheConfig {
+    /// 创建一个新的 EhCacheConfig 实例
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        EhCacheConfig {
+            dir: dir.into(),
+            ttl: EhCacheTtl::default(),
+            stale_secs: Self::default_stale_secs(),
+            max_size: Self::default_max_size(),
+            offline: false,
+        }
+    }
+
+    fn default_stale_secs() -> u64 {
+        600
+    }
+
+    fn default_max_size() -> u64 {
+        256 * 1024 * 1024
+    }
+}
+
+/// 缓存条目的新鲜程度
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EhCacheState {
+    /// 在有效期内
+    Fresh,
+    /// 已过期，但仍可在后台刷新时返回
+    Stale,
+    /// 已过期，仅在离线模式下返回
+    Expired,
+}
+
+/// 缓存文件的内容
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    url: String,
+    stored_at: u64,
+    body: String,
+}
+
+/// 磁盘响应缓存，可在多个客户端间共享
+pub struct EhCache {
+    config: EhCacheConfig,
+    size: Mutex<u64>,
+    revalidating: Mutex<HashSet<String>>,
+}
+
+impl EhCache {
+    /// 打开缓存目录，目录不存在时创建
+    pub fn open(config: EhCacheConfig) -> EhResult<Self> {
+        fs::create_dir_all(&config.dir)?;
+        let cache = EhCache {
+            config,
+            size: Mutex::new(0),
+            revalidating: Mutex::new(HashSet::new()),
+        };
+        let size = cache.files()?.iter().map(|(_, _, len)| len).sum();
+        *cache.size.lock().unwrap() = size;
+        Ok(cache)
+    }
+
+    /// 缓存设置
+    pub fn config(&self) -> &EhCacheConfig {
+        &self.config
+    }
+
+    /// 计算请求的缓存键与资源类型，不应缓存的请求返回 None
+    ///
+    /// 只缓存 GET 请求与 API 的 POST 请求，论坛页面（登录等）不会被缓存。`identity` 为发送请求的
+    /// 用户 ID，不同用户的响应（收藏、登录后的页面等）使用不同的缓存键。
+    pub fn key(
+        request: &Request,
+        endpoints: &EhEndpoints,
+        identity: Option<&str>,
+    ) -> Option<(String, EhCacheKind)> {
+        let url = request.url();
+        let host = url.host_str();
+        if host.is_some() && host == endpoints.forums_host().as_deref() {
+            return None;
+        }
+        let kind = if host.is_some() && host == endpoints.api_host().as_deref()
+            || url.path().ends_with("/api.php")
+        {
+            EhCacheKind::Api
+        } else if url.path().starts_with("/g/") {
+            EhCacheKind::Gallery
+        } else {
+            EhCacheKind::Page
+        };
+        let body = match *request.method() {
+            Method::GET => &[][..],
+            Method::POST if kind == EhCacheKind::Api => request.body()?.as_bytes()?,
+            _ => return None,
+        };
+        let mut hash = Fnv1a::new();
+        hash.write(request.method().as_str().as_bytes());
+        hash.write(b" ");
+        hash.write(url.as_str().as_bytes());
+        hash.write(b"\n");
+        hash.write(body);
+        if let Some(identity) = identity {
+            hash.write(b"\n");
+            hash.write(identity.as_bytes());
+        }
+        Some((format!("{:016x}", hash.finish()), kind))
+    }
+
+    /// 读取缓存的响应与其新鲜程度
+    pub fn get(&self, key: &str, kind: EhCacheKind) -> Option<(String, EhCacheState)> {
+        let file = File::open(self.path(key)).ok()?;
+        let entry: CacheEntry = serde_json::from_reader(BufReader::new(file)).ok()?;
+        let age = Duration::from_secs(now().saturating_sub(entry.stored_at));
+        let ttl = self.config.ttl.get(kind);
+        let state = if age < ttl {
+            EhCacheState::Fresh
+        } else if age < ttl + Duration::from_secs(self.config.stale_secs) {
+            EhCacheState::Stale
+        } else {
+            EhCacheState::Expired
+        };
+        Some((entry.body, state))
+    }
+
+    /// 写入响应，超出大小上限时删除最早写入的缓存
+    pub fn put(&self, key: &str, url: &str, body: &str) -> EhResult<()> {
+        let path = self.path(key);
+        let old = fs::metadata(&path).map(|meta| meta.len()).unwrap_or(0);
+        let tmp = path.with_extension("json.tmp");
+        {
+            let mut writer = BufWriter::new(File::create(&tmp)?);
+            let entry = CacheEntry {
+                url: url.to_string(),
+                stored_at: now(),
+                body: body.to_string(),
+            };
+            serde_json::to_writer(&mut writer, &entry)
+                .map_err(|err| EhError::parse("cache entry", err.to_string()))?;
+        }
+        fs::rename(&tmp, &path)?;
+        let len = fs::metadata(&path)?.len();
+        let size = {
+            let mut size = self.size.lock().unwrap();
+            *size = size.saturating_sub(old) + len;
+            *size
+        };
+        if size > self.config.max_size {
+            self.evict()?;
+        }
+        Ok(())
+    }
+
+    /// 删除所有缓存
+    pub fn clear(&self) -> EhResult<()> {
+        for (path, _, _) in self.files()? {
+            fs::remove_file(path)?;
+        }
+        *self.size.lock().unwrap() = 0;
+        Ok(())
+    }
+
+    /// 登记后台刷新，已有相同的刷新正在进行时返回 false
+    pub(crate) fn begin_revalidate(&self, key: &str) -> bool {
+        self.revalidating.lock().unwrap().insert(key.to_string())
+    }
+
+    /// 注销后台刷新
+    pub(crate) fn end_revalidate(&self, key: &str) {
+        self.revalidating.lock().unwrap().remove(key);
+    }
+
+    /// 删除最早写入的缓存，直到缓存目录小于大小上限
+    fn evict(&self) -> EhResult<()> {
+        let mut files = self.files()?;
+        files.sort_by_key(|(_, modified, _)| *modified);
+        let mut size: u64 = files.iter().map(|(_, _, len)| len).sum();
+        for (path, _, len) in files {
+            if size <= self.config.max_size {
+                break;
+            }
+            fs::remove_file(path)?;
+            size -= len;
+        }
+        *self.size.lock().unwrap() = size;
+        Ok(())
+    }
+
+    /// 列出缓存文件及其修改时间与大小
+    fn files(&self) -> EhResult<Vec<(PathBuf, SystemTime, u64)>> {
+        let mut files = vec![];
+        for entry in fs::read_dir(&self.config.dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            let meta = entry.metadata()?;
+            files.push((path, meta.modified()?, meta.len()));
+        }
+        Ok(files)
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.config.dir.join(format!("{}.json", key))
+    }
+}
+
+/// 当前 Unix 时间戳，单位为秒
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+/// FNV-1a 哈希，用于生成在不同版本间保持稳定的缓存文件名
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new() -> Self {
+        Fnv1a(0xcbf29ce484222325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use reqwest::{Client, Url};
+
+    use crate::{
+        client::{
+            client::EhClient,
+            config::EhClientConfig,
+            endpoints::EhEndpoints,
+            test::{TestResponse, TestServer},
+        },
+        error::EhError,
+    };
+
+    use super::{EhCache, EhCacheConfig, EhCacheKind, EhCacheMode, EhCacheState, EhCacheTtl};
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("libeh-cache-{}-{}", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_cache_key() {
+        let client = Client::new();
+        let endpoints = EhEndpoints::default();
+        let key = |request: reqwest::RequestBuilder| {
+            EhCache::key(&request.build().unwrap(), &endpoints, None)
+        };
+        let gallery = key(client.get("https://e-hentai.org/g/1/abcdef0123/")).unwrap();
+        assert_eq!(gallery.1, EhCacheKind::Gallery);
+        let search = key(client.get("https://e-hentai.org/?f_search=a")).unwrap();
+        assert_eq!(search.1, EhCacheKind::Page);
+        let api = |body: &str| {
+            key(client
+                .post("https://api.e-hentai.org/api.php")
+                .body(body.to_string()))
+        };
+        let (a, kind) = api("{\"gidlist\":[[1,\"a\"]]}").unwrap();
+        let (b, _) = api("{\"gidlist\":[[2,\"b\"]]}").unwrap();
+        assert_eq!(kind, EhCacheKind::Api);
+        assert_ne!(a, b);
+        assert!(key(client.post("https://e-hentai.org/g/1/abcdef0123/")).is_none());
+        assert!(key(client.get("https://forums.e-hentai.org/index.php")).is_none());
+
+        let request = client
+            .get("https://e-hentai.org/favorites.php")
+            .build()
+            .unwrap();
+        let a = EhCache::key(&request, &endpoints, Some("1")).unwrap();
+        let b = EhCache::key(&request, &endpoints, Some("2")).unwrap();
+        let anonymous = EhCache::key(&request, &endpoints, None).unwrap();
+        assert_ne!(a.0, b.0);
+        assert_ne!(a.0, anonymous.0);
+
+        let endpoints = EhEndpoints {
+            api: "https://eh.example.org/json".into(),
+            forums: "https://bbs.example.org/".into(),
+            ..Default::default()
+        };
+        let key = |url: &str| EhCache::key(&client.get(url).build().unwrap(), &endpoints, None);
+        assert_eq!(
+            key("https://eh.example.org/json").unwrap().1,
+            EhCacheKind::Api
+        );
+        assert!(key("https://bbs.example.org/index.php").is_none());
+        assert!(key("https://forums.e-hentai.org/index.php").is_some());
+    }
+
+    #[test]
+    fn test_cache_eviction() {
+        let dir = temp_dir("evict");
+        let cache = EhCache::open(EhCacheConfig {
+            max_size: 300,
+            ..EhCacheConfig::new(&dir)
+        })
+        .unwrap();
+        let body = "x".repeat(100);
+        for key in ["a", "b", "c"] {
+            cache.put(key, "https://e-hentai.org/", &body).unwrap();
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(cache.get("a", EhCacheKind::Page).is_none());
+        assert_eq!(
+            cache.get("c", EhCacheKind::Page),
+            Some((body, EhCacheState::Fresh))
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cache_fresh_and_offline() {
+        let server = TestServer::start(vec![("/", vec![TestResponse::new(200, "cached")])]).await;
+        let dir = temp_dir("offline");
+        let config = EhClientConfig {
+            cache: Some(EhCacheConfig::new(&dir)),
+            ..Default::default()
+        };
+        let client = EhClient::new(config);
+        assert_eq!(client.get_html(server.url("/a")).await.unwrap(), "cached");
+        assert_eq!(client.get_html(server.url("/a")).await.unwrap(), "cached");
+        assert_eq!(server.requests().len(), 1);
+
+        let offline = client.with_cache_mode(EhCacheMode::Offline);
+        assert_eq!(offline.get_html(server.url("/a")).await.unwrap(), "cached");
+        assert!(matches!(
+            offline.get_html(server.url("/b")).await,
+            Err(EhError::CacheMiss(_))
+        ));
+        let bypass = client.with_cache_mode(EhCacheMode::Bypass);
+        bypass.get_html(server.url("/a")).await.unwrap();
+        assert_eq!(server.requests().len(), 2);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cache_stale_while_revalidate() {
+        let server = TestServer::start(vec![(
+            "/",
+            vec![TestResponse::new(200, "v1"), TestResponse::new(200, "v2")],
+        )])
+        .await;
+        let dir = temp_dir("stale");
+        let config = EhClientConfig {
+            cache: Some(EhCacheConfig {
+                ttl: EhCacheTtl {
+                    page_secs: 0,
+                    ..Default::default()
+                },
+                ..EhCacheConfig::new(&dir)
+            }),
+            ..Default::default()
+        };
+        let client = EhClient::new(config);
+        let url: Url = server.url("/");
+        assert_eq!(client.get_html(url.clone()).await.unwrap(), "v1");
+        assert_eq!(client.get_html(url.clone()).await.unwrap(), "v1");
+        for _ in 0..100 {
+            if server.requests().len() == 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(server.requests().len(), 2);
+        assert_eq!(client.get_html(url).await.unwrap(), "v2");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
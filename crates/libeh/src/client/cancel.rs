@@ -0,0 +1,169 @@
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use tokio::sync::Notify;
+
+use crate::error::{EhError, EhResult};
+
+#[derive(Debug, Default)]
+struct CancelState {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+/// 取消令牌，克隆的令牌共享同一个取消状态
+///
+/// 通过 [`EhClient::with_cancel`](super::client::EhClient::with_cancel) 绑定到客户端后，
+/// 取消会中止正在等待频率限制、重试或读取响应的请求，并以 [`EhError::Cancelled`] 失败。
+/// 分页、批量等长时间运行的操作在取消时返回 [`EhOutcome::Cancelled`] 与已完成的部分结果。
+#[derive(Debug, Clone, Default)]
+pub struct EhCancelToken {
+    state: Arc<CancelState>,
+}
+
+impl EhCancelToken {
+    /// 创建一个未取消的令牌
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 取消令牌，唤醒所有等待中的操作，重复取消没有效果
+    pub fn cancel(&self) {
+        if !self.state.cancelled.swap(true, Ordering::SeqCst) {
+            self.state.notify.notify_waiters();
+        }
+    }
+
+    /// 令牌是否已被取消
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// 已取消时返回 [`EhError::Cancelled`]
+    pub fn check(&self) -> EhResult<()> {
+        if self.is_cancelled() {
+            return Err(EhError::Cancelled);
+        }
+        Ok(())
+    }
+
+    /// 等待令牌被取消
+    pub async fn cancelled(&self) {
+        let notified = self.state.notify.notified();
+        tokio::pin!(notified);
+        notified.as_mut().enable();
+        if self.is_cancelled() {
+            return;
+        }
+        notified.await;
+    }
+
+    /// 运行一个操作，令牌被取消时丢弃该操作并返回 [`EhError::Cancelled`]
+    pub async fn run<T>(&self, future: impl Future<Output = EhResult<T>>) -> EhResult<T> {
+        tokio::select! {
+            biased;
+            _ = self.cancelled() => Err(EhError::Cancelled),
+            result = future => result,
+        }
+    }
+}
+
+/// 可取消操作的结果，取消时附带已完成的部分结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum EhOutcome<T> {
+    /// 操作全部完成
+    Complete(T),
+    /// 操作被取消，附带取消前已完成的部分
+    Cancelled(T),
+}
+
+impl<T> EhOutcome<T> {
+    /// 按令牌的状态包装结果
+    pub fn from_token(token: Option<&EhCancelToken>, value: T) -> Self {
+        match token {
+            Some(token) if token.is_cancelled() => EhOutcome::Cancelled(value),
+            _ => EhOutcome::Complete(value),
+        }
+    }
+
+    /// 操作是否被取消
+    pub fn is_cancelled(&self) -> bool {
+        matches!(self, EhOutcome::Cancelled(_))
+    }
+
+    /// 取出结果，不区分是否被取消
+    pub fn into_inner(self) -> T {
+        match self {
+            EhOutcome::Complete(value) | EhOutcome::Cancelled(value) => value,
+        }
+    }
+
+    /// 转换结果，保留取消状态
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> EhOutcome<U> {
+        match self {
+            EhOutcome::Complete(value) => EhOutcome::Complete(f(value)),
+            EhOutcome::Cancelled(value) => EhOutcome::Cancelled(f(value)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use async_trait::async_trait;
+    use reqwest::{Request, Url};
+
+    use crate::{
+        client::{
+            client::EhClient,
+            config::EhClientConfig,
+            transport::{EhResponse, EhTransport},
+        },
+        error::{EhError, EhResult},
+    };
+
+    use super::{EhCancelToken, EhOutcome};
+
+    /// 响应前等待一段时间的传输层
+    struct SlowTransport(Duration);
+
+    #[async_trait]
+    impl EhTransport for SlowTransport {
+        async fn execute(&self, _request: Request) -> EhResult<EhResponse> {
+            tokio::time::sleep(self.0).await;
+            Ok(EhResponse::new(200, "slow"))
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_cancel_request() {
+        let transport = Arc::new(SlowTransport(Duration::from_secs(60)));
+        let token = EhCancelToken::new();
+        let client = EhClient::new_with_transport(EhClientConfig::default(), transport)
+            .with_cancel(token.clone());
+        let url = Url::parse("https://e-hentai.org/g/1/abc/").unwrap();
+        let canceller = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            canceller.cancel();
+        });
+        let result = client.get_html(url.clone()).await;
+        assert!(matches!(result, Err(EhError::Cancelled)));
+        assert!(token.is_cancelled());
+        assert!(matches!(
+            client.get_html(url).await,
+            Err(EhError::Cancelled)
+        ));
+
+        let outcome = EhOutcome::from_token(Some(&token), vec![1, 2]);
+        assert!(outcome.is_cancelled());
+        assert_eq!(outcome.map(|v| v.len()).into_inner(), 2);
+        assert_eq!(EhOutcome::from_token(None, 1), EhOutcome::Complete(1));
+    }
+}
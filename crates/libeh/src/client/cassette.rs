@@ -0,0 +1,257 @@
+use std::{
+    fs::{self, File},
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use async_trait::async_trait;
+use reqwest::Request;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{EhError, EhResult};
+
+use super::transport::{EhRecordedRequest, EhResponse, EhTransport};
+
+/// 一次请求与其响应
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EhInteraction {
+    /// 请求
+    pub request: EhRecordedRequest,
+    /// 响应
+    pub response: EhResponse,
+}
+
+/// 录制文件的内容
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Cassette {
+    interactions: Vec<EhInteraction>,
+}
+
+/// 录制时隐藏的表单字段，即登录请求中的用户名与密码
+const MASKED_FORM_FIELDS: [&str; 2] = ["UserName", "PassWord"];
+
+/// 录制与回放传输层
+///
+/// 录制模式下将请求转发给内部传输层，并把每次请求与响应写入录制文件；
+/// 回放模式下从录制文件中按顺序查找请求方法、URL 与请求体都相同的响应，不访问网络。
+/// 录制文件中的用户名、密码与 `Set-Cookie` 的值被隐藏，回放时按隐藏后的请求体匹配。
+pub struct CassetteTransport {
+    path: PathBuf,
+    inner: Option<Box<dyn EhTransport>>,
+    interactions: Mutex<Vec<EhInteraction>>,
+    used: Mutex<Vec<bool>>,
+}
+
+impl CassetteTransport {
+    /// 创建录制模式的传输层，录制文件会被覆盖
+    pub fn record(path: impl AsRef<Path>, inner: impl EhTransport + 'static) -> Self {
+        CassetteTransport {
+            path: path.as_ref().to_path_buf(),
+            inner: Some(Box::new(inner)),
+            interactions: Mutex::new(vec![]),
+            used: Mutex::new(vec![]),
+        }
+    }
+
+    /// 从录制文件创建回放模式的传输层
+    pub fn replay(path: impl AsRef<Path>) -> EhResult<Self> {
+        let path = path.as_ref().to_path_buf();
+        let reader = BufReader::new(File::open(&path)?);
+        let cassette: Cassette = serde_json::from_reader(reader)
+            .map_err(|err| EhError::parse("cassette", err.to_string()))?;
+        let used = vec![false; cassette.interactions.len()];
+        Ok(CassetteTransport {
+            path,
+            inner: None,
+            interactions: Mutex::new(cassette.interactions),
+            used: Mutex::new(used),
+        })
+    }
+
+    /// 获取已录制或已加载的所有请求与响应
+    pub fn interactions(&self) -> Vec<EhInteraction> {
+        self.interactions.lock().unwrap().clone()
+    }
+
+    /// 将录制的内容写入录制文件
+    pub fn save(&self) -> EhResult<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let tmp = self.path.with_extension("json.tmp");
+        {
+            let writer = BufWriter::new(File::create(&tmp)?);
+            let cassette = Cassette {
+                interactions: self.interactions(),
+            };
+            serde_json::to_writer_pretty(writer, &cassette)
+                .map_err(|err| EhError::parse("cassette", err.to_string()))?;
+        }
+        fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+
+    /// 查找第一个未使用且与请求相同的响应
+    fn find(&self, request: &EhRecordedRequest) -> Option<EhResponse> {
+        let interactions = self.interactions.lock().unwrap();
+        let mut used = self.used.lock().unwrap();
+        let index =
+            (0..interactions.len()).find(|&i| !used[i] && interactions[i].request == *request)?;
+        used[index] = true;
+        Some(interactions[index].response.clone())
+    }
+}
+
+/// 隐藏请求体中的用户名与密码表单字段
+fn redact_request(mut request: EhRecordedRequest) -> EhRecordedRequest {
+    if request.body.is_empty() {
+        return request;
+    }
+    request.body = request
+        .body
+        .split('&')
+        .map(|pair| {
+            let name = pair.split_once('=').map_or(pair, |(name, _)| name);
+            match MASKED_FORM_FIELDS.contains(&name) {
+                true => format!("{}=***", name),
+                false => pair.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("&");
+    request
+}
+
+/// 隐藏响应中 `Set-Cookie` 的值，保留 Cookie 名称与属性，避免录制文件泄露身份验证信息
+fn redact(response: &EhResponse) -> EhResponse {
+    let mut response = response.clone();
+    for (key, value) in response.headers.iter_mut() {
+        if !key.eq_ignore_ascii_case("set-cookie") {
+            continue;
+        }
+        let (pair, attrs) = value.split_once(';').unwrap_or((value.as_str(), ""));
+        let name = pair.split_once('=').map_or(pair, |(name, _)| name).trim();
+        *value = match attrs.is_empty() {
+            true => format!("{}=***", name),
+            false => format!("{}=***;{}", name, attrs),
+        };
+    }
+    response
+}
+
+#[async_trait]
+impl EhTransport for CassetteTransport {
+    async fn execute(&self, request: Request) -> EhResult<EhResponse> {
+        let recorded = redact_request(EhRecordedRequest::from(&request));
+        let Some(inner) = &self.inner else {
+            return self.find(&recorded).ok_or_else(|| {
+                EhError::invalid_input(format!(
+                    "No recorded response for {} {}",
+                    recorded.method, recorded.url
+                ))
+            });
+        };
+        let response = inner.execute(request).await?;
+        self.interactions.lock().unwrap().push(EhInteraction {
+            request: recorded,
+            response: redact(&response),
+        });
+        self.save()?;
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use reqwest::Url;
+
+    use crate::{
+        client::{
+            client::EhClient,
+            config::EhClientConfig,
+            endpoints::EhEndpoints,
+            transport::{EhResponse, FixtureTransport},
+        },
+        error::EhError,
+    };
+
+    use super::CassetteTransport;
+
+    #[tokio::test]
+    async fn test_cassette_record_replay() {
+        let path = std::env::temp_dir().join(format!("libeh-cassette-{}.json", std::process::id()));
+        let api = Url::parse("https://api.e-hentai.org/api.php").unwrap();
+        let fixture = FixtureTransport::new()
+            .post(api.as_str(), EhResponse::new(200, r#"{"gmetadata":[]}"#))
+            .get(
+                "https://e-hentai.org/",
+                EhResponse::new(200, "index")
+                    .header(
+                        "Set-Cookie",
+                        "ipb_pass_hash=secret; Path=/; Domain=.e-hentai.org",
+                    )
+                    .header("Set-Cookie", "sk=abc"),
+            );
+        let recorder = Arc::new(CassetteTransport::record(&path, fixture));
+        let client = EhClient::new_with_transport(EhClientConfig::default(), recorder.clone());
+        let json: serde_json::Value = client.post_json(api.clone(), "{}").await.unwrap();
+        assert_eq!(json["gmetadata"], serde_json::json!([]));
+        let index = Url::parse("https://e-hentai.org/").unwrap();
+        assert_eq!(client.get_html(index.clone()).await.unwrap(), "index");
+        assert_eq!(recorder.interactions().len(), 2);
+        let cookie = ("ipb_pass_hash".to_string(), "secret".to_string());
+        assert!(client.cookies(&index).contains(&cookie));
+        let recorded = std::fs::read_to_string(&path).unwrap();
+        assert!(!recorded.contains("secret") && !recorded.contains("abc"));
+        let cookies: Vec<_> = recorder.interactions()[1]
+            .response
+            .get_headers("set-cookie")
+            .map(str::to_string)
+            .collect();
+        assert_eq!(
+            cookies,
+            vec!["ipb_pass_hash=***; Path=/; Domain=.e-hentai.org", "sk=***"]
+        );
+
+        let player = Arc::new(CassetteTransport::replay(&path).unwrap());
+        let client = EhClient::new_with_transport(EhClientConfig::default(), player);
+        assert_eq!(client.get_html(index).await.unwrap(), "index");
+        let json: serde_json::Value = client.post_json(api.clone(), "{}").await.unwrap();
+        assert_eq!(json["gmetadata"], serde_json::json!([]));
+        let result: Result<serde_json::Value, _> = client.post_json(api, "{}").await;
+        assert!(matches!(result, Err(EhError::InvalidInput(_))));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cassette_login_redacted() {
+        let path =
+            std::env::temp_dir().join(format!("libeh-cassette-login-{}.json", std::process::id()));
+        let login = EhEndpoints::default().login().unwrap();
+        let fixture = FixtureTransport::new().post(
+            login.as_str(),
+            EhResponse::new(200, "<html>Thanks, you are now logged in</html>")
+                .header("Set-Cookie", "ipb_member_id=123456; Path=/")
+                .header("Set-Cookie", "ipb_pass_hash=0123456789abcdef; Path=/"),
+        );
+        let recorder = Arc::new(CassetteTransport::record(&path, fixture));
+        let client = EhClient::new_with_transport(EhClientConfig::default(), recorder.clone());
+        client.login("someone", "hunter2").await.unwrap();
+        let recorded = std::fs::read_to_string(&path).unwrap();
+        assert!(!recorded.contains("hunter2") && !recorded.contains("someone"));
+        assert!(recorder.interactions()[0]
+            .request
+            .body
+            .contains("PassWord=***"));
+
+        let player = Arc::new(CassetteTransport::replay(&path).unwrap());
+        let client = EhClient::new_with_transport(EhClientConfig::default(), player);
+        // 回放时 Cookie 的值同样被隐藏
+        let auth = client.login("someone", "hunter2").await.unwrap();
+        assert_eq!(auth.ipb_pass_hash, "***");
+        std::fs::remove_file(path).unwrap();
+    }
+}
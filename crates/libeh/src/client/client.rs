@@ -1,139 +1,590 @@
-use reqwest::{cookie::Jar, redirect, Client, Proxy, Url};
+use std::{sync::Arc, time::Instant};
+
+use reqwest::{
+    cookie::CookieStore,
+    header::{HeaderMap, AUTHORIZATION, CONTENT_LENGTH, CONTENT_TYPE, COOKIE, PROXY_AUTHORIZATION},
+    redirect, Client, Method, Proxy, Request, RequestBuilder, Url,
+};
 use serde::de::DeserializeOwned;
+use tracing::{field, Instrument, Span};
 
 use crate::dto::{keyword::Keyword, search_offset::Offset, site::Site};
+use crate::error::{EhError, EhResult, SiteError};
 use crate::url::search::SearchBuilder;
 
-use super::config::EhClientConfig;
+use super::{
+    cache::{EhCache, EhCacheMode, EhCacheState},
+    cancel::EhCancelToken,
+    config::EhClientConfig,
+    cookie::EhCookieJar,
+    endpoints::EhEndpoints,
+    http::EhHttpConfig,
+    limiter::{EhPriority, EhRateLimiter},
+    metrics::{request_kind, EhMetrics},
+    middleware::{EhMiddleware, EhRequestInfo, EhSiteErrorMiddleware},
+    pool::{EhProxyPool, ProxyPoolTransport},
+    proxy::{select_proxy, EhClientProxy, EhProxyRule},
+    resolve::EhResolver,
+    retry::EhRetryPolicy,
+    transport::{EhResponse, EhTransport, ReqwestTransport},
+};
 
 #[derive(Clone)]
 pub struct EhClient {
-    site: Site,
-    client: Client,
+    pub(super) site: Site,
+    pub(super) endpoints: Arc<EhEndpoints>,
+    pub(super) client: Client,
+    transport: Arc<dyn EhTransport>,
+    pub(super) jar: Arc<EhCookieJar>,
+    limiter: Option<Arc<EhRateLimiter>>,
+    priority: EhPriority,
+    retry: EhRetryPolicy,
+    cache: Option<Arc<EhCache>>,
+    cache_mode: EhCacheMode,
+    proxy_pool: Option<Arc<EhProxyPool>>,
+    max_redirects: u32,
+    headers: Arc<HeaderMap>,
+    middlewares: Arc<Vec<Arc<dyn EhMiddleware>>>,
+    metrics: Option<Arc<EhMetrics>>,
+    cancel: Option<EhCancelToken>,
 }
 
 impl EhClient {
+    /// 创建一个新的 EhClient 实例，配置无效时 panic
     pub fn new(config: EhClientConfig) -> Self {
-        let mut builder = Client::builder()
-            .redirect(redirect::Policy::limited(20))
-            .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/121.0.0.0 Safari/537.36 Edg/121.0.0.0");
-        if let Some(proxy) = config.proxy.clone() {
-            let proxy = Proxy::all(proxy.to_string());
-            if let Ok(proxy) = proxy {
-                builder = builder.proxy(proxy);
-            }
+        match Self::try_new(config) {
+            Ok(client) => client,
+            Err(err) => panic!("Error: {}", err),
+        }
+    }
+
+    /// 创建一个新的 EhClient 实例，代理、连接设置等配置无效时返回错误
+    pub fn try_new(config: EhClientConfig) -> EhResult<Self> {
+        config.validate()?;
+        let config = config.load_credentials()?;
+        let resolver = match &config.resolve {
+            Some(resolve) => Some(Arc::new(EhResolver::new(resolve.clone())?)),
+            None => None,
+        };
+        let http = &config.http;
+        let rules = config.proxy_rules.clone();
+        let client = Self::build_client(http, resolver.as_ref(), &rules, config.proxy.clone())?;
+        let direct = ReqwestTransport::new(client.clone()).with_read_timeout(http.read_timeout());
+        let Some(pool) = &config.proxy_pool else {
+            return Ok(Self::build(config, client, Arc::new(direct)));
+        };
+        let transports = pool
+            .proxies
+            .iter()
+            .map(|proxy| {
+                let client = Self::build_client(http, resolver.as_ref(), &[], Some(proxy.clone()))?;
+                Ok(ReqwestTransport::new(client).with_read_timeout(http.read_timeout()))
+            })
+            .collect::<EhResult<Vec<_>>>()?;
+        let pool = Arc::new(EhProxyPool::new(pool.clone()));
+        let transport = Arc::new(ProxyPoolTransport::new(
+            pool.clone(),
+            transports,
+            rules,
+            direct,
+        ));
+        let mut client = Self::build(config, client, transport);
+        client.proxy_pool = Some(pool);
+        Ok(client)
+    }
+
+    /// 按连接设置创建不跟随重定向的 reqwest 客户端，按规则选择代理
+    fn build_client(
+        http: &EhHttpConfig,
+        resolver: Option<&Arc<EhResolver>>,
+        rules: &[EhProxyRule],
+        default: Option<EhClientProxy>,
+    ) -> EhResult<Client> {
+        let mut builder = http.apply(Client::builder().redirect(redirect::Policy::none()))?;
+        if let Some(resolver) = resolver {
+            builder = builder.dns_resolver(resolver.clone());
+        }
+        if default.is_some() || !rules.is_empty() {
+            let rules = rules.to_vec();
+            builder = builder.proxy(Proxy::custom(move |url| {
+                select_proxy(&rules, default.as_ref(), url).and_then(|proxy| proxy.to_url().ok())
+            }));
         }
+        Ok(builder.build()?)
+    }
+
+    /// 使用自定义的传输层创建客户端，如 [`FixtureTransport`](super::transport::FixtureTransport)
+    /// 或 [`CassetteTransport`](super::cassette::CassetteTransport)，配置中的代理设置不会生效
+    ///
+    /// 该方法不返回错误，凭据文件读取或解密失败时记录警告并以未登录的状态创建客户端，
+    /// 配置的请求头无效时记录警告并不附带配置的请求头。需要在配置无效时失败的，
+    /// 可以先调用 [`EhClientConfig::validate`] 与 [`EhClientConfig::load_credentials`]。
+    pub fn new_with_transport(config: EhClientConfig, transport: Arc<dyn EhTransport>) -> Self {
+        let config = config.clone().load_credentials().unwrap_or_else(|err| {
+            log::warn!("Failed to load credentials: {}", err);
+            config
+        });
+        Self::build(config, Client::new(), transport)
+    }
+
+    fn build(config: EhClientConfig, client: Client, transport: Arc<dyn EhTransport>) -> Self {
+        let jar = match &config.cookie_store {
+            Some(store) => {
+                let path = store.path(config.auth.as_ref());
+                let jar = EhCookieJar::load(&path).unwrap_or_else(|err| {
+                    log::warn!("Failed to load cookies from {}: {}", path.display(), err);
+                    EhCookieJar::new()
+                });
+                // 使用加密凭据文件时，身份验证 Cookie 不以明文写入 Cookie 文件
+                match config.credentials {
+                    Some(_) => jar.skip_persisting(&["ipb_pass_hash", "igneous"]),
+                    None => jar,
+                }
+            }
+            None => EhCookieJar::new(),
+        };
         if let Some(auth) = config.auth.clone() {
-            let jar = Jar::default();
-            let auth_vec = auth.to_vec();
-            for (key, value) in auth_vec {
-                jar.add_cookie_str(&format!("{}={}", key, value), &config.site.into());
+            // 身份验证 Cookie 作用于站点的主域名，论坛等子域名共用同一组 Cookie
+            for site in [Site::Eh, Site::Ex] {
+                let Ok(url) = config.endpoints.site(site) else {
+                    continue;
+                };
+                let domain = url
+                    .domain()
+                    .map(|domain| format!("; Domain={}", domain))
+                    .unwrap_or_default();
+                // 配置的身份验证信息覆盖已保存的同名 Cookie
+                for (key, value) in auth.to_vec() {
+                    let cookie = format!("{}={}{}; Path=/", key, value, domain);
+                    jar.add_cookie_str(&cookie, &url);
+                }
             }
-            println!("Cookie: {:?}", jar);
-            builder = builder.cookie_store(true).cookie_provider(jar.into());
         }
+        let cache = config.cache.as_ref().and_then(|cache| {
+            EhCache::open(cache.clone())
+                .map_err(|err| log::warn!("Failed to open cache {}: {}", cache.dir.display(), err))
+                .ok()
+        });
+        let cache_mode = match &config.cache {
+            Some(cache) if cache.offline => EhCacheMode::Offline,
+            _ => EhCacheMode::Normal,
+        };
+        let headers = config.http.header_map().unwrap_or_else(|err| {
+            log::warn!("Ignoring configured headers: {}", err);
+            HeaderMap::new()
+        });
+        let endpoints = Arc::new(config.endpoints);
+        let middlewares: Vec<Arc<dyn EhMiddleware>> =
+            vec![Arc::new(EhSiteErrorMiddleware::new(endpoints.clone()))];
+        EhClient {
+            client,
+            transport,
+            site: config.site,
+            jar: Arc::new(jar),
+            limiter: config.rate_limit.map(|rate_limit| {
+                Arc::new(EhRateLimiter::new(rate_limit).with_endpoints(&endpoints))
+            }),
+            endpoints,
+            priority: EhPriority::default(),
+            retry: config.retry.unwrap_or_else(EhRetryPolicy::none),
+            cache: cache.map(Arc::new),
+            cache_mode,
+            proxy_pool: None,
+            max_redirects: config.http.max_redirects,
+            headers: Arc::new(headers),
+            middlewares: Arc::new(middlewares),
+            metrics: config.metrics.then(|| Arc::new(EhMetrics::new())),
+            cancel: None,
+        }
+    }
 
-        let client = builder.build();
+    /// 获取客户端使用的代理池，未配置代理池时返回 None
+    pub fn proxy_pool(&self) -> Option<&EhProxyPool> {
+        self.proxy_pool.as_deref()
+    }
 
-        match client {
-            Ok(client) => EhClient {
-                client,
-                site: config.site,
-            },
-            Err(err) => panic!("Error: {}", err),
+    /// 获取客户端的请求指标，配置中未启用指标时返回 None
+    pub fn metrics(&self) -> Option<&EhMetrics> {
+        self.metrics.as_deref()
+    }
+
+    /// 返回一个以指定优先级发送请求的客户端，与原客户端共享连接池与频率限制
+    pub fn with_priority(&self, priority: EhPriority) -> Self {
+        let mut client = self.clone();
+        client.priority = priority;
+        client
+    }
+
+    /// 返回一个使用指定重试策略的客户端，与原客户端共享连接池与频率限制
+    pub fn with_retry(&self, retry: EhRetryPolicy) -> Self {
+        let mut client = self.clone();
+        client.retry = retry;
+        client
+    }
+
+    /// 返回一个在已有中间件之后追加指定中间件的客户端，与原客户端共享连接池与频率限制
+    pub fn with_middleware(&self, middleware: impl EhMiddleware + 'static) -> Self {
+        let mut middlewares = self.middlewares.as_ref().clone();
+        middlewares.push(Arc::new(middleware));
+        let mut client = self.clone();
+        client.middlewares = Arc::new(middlewares);
+        client
+    }
+
+    /// 返回一个绑定取消令牌的客户端，与原客户端共享连接池与频率限制
+    ///
+    /// 令牌取消后，正在进行与之后发出的网络请求均以 [`EhError::Cancelled`] 失败。
+    pub fn with_cancel(&self, token: EhCancelToken) -> Self {
+        let mut client = self.clone();
+        client.cancel = Some(token);
+        client
+    }
+
+    /// 客户端绑定的取消令牌
+    pub fn cancel_token(&self) -> Option<&EhCancelToken> {
+        self.cancel.as_ref()
+    }
+
+    /// 返回一个以指定方式使用缓存的客户端，与原客户端共享连接池与缓存
+    pub fn with_cache_mode(&self, mode: EhCacheMode) -> Self {
+        let mut client = self.clone();
+        client.cache_mode = mode;
+        client
+    }
+
+    /// 返回一个跳过缓存的客户端，离线模式下保持离线
+    pub(super) fn without_cache(&self) -> Self {
+        match self.cache_mode {
+            EhCacheMode::Normal => self.with_cache_mode(EhCacheMode::Bypass),
+            _ => self.clone(),
         }
     }
 
     /// 不包含高级选项的搜索
-    pub async fn search(
-        &self,
-        keywords: Vec<Keyword>,
-        offset: Option<Offset>,
-    ) -> Result<String, String> {
-        let mut builder = SearchBuilder::new(self.site).add_keywords(keywords);
+    pub async fn search(&self, keywords: Vec<Keyword>, offset: Option<Offset>) -> EhResult<String> {
+        let mut builder = SearchBuilder::new(self.site)
+            .base_url(self.endpoints.site(self.site)?)
+            .add_keywords(keywords);
         if let Some(offset) = offset {
             builder = builder.offset(offset);
         }
         let url = builder.build()?;
-        let text = match self.get_html(url).await {
-            Ok(text) => text,
-            Err(err) => {
-                return Err(format!("Error: {}", err));
-            }
-        };
-        Ok(text)
+        self.get_html(url).await
     }
 
-    pub async fn get_html(&self, url: Url) -> Result<String, String> {
-        let res: Result<reqwest::Response, reqwest::Error> = self.client.get(url).send().await;
-        let res = match res {
-            Ok(res) => res,
-            Err(err) => {
-                return Err(format!("Error: {}", err));
+    /// 获取页面 HTML 文本
+    pub async fn get_html(&self, url: Url) -> EhResult<String> {
+        self.fetch_text(self.client.get(url)).await
+    }
+
+    /// 获取 JSON 数据并反序列化
+    pub async fn get_json<T>(&self, url: Url) -> EhResult<T>
+    where
+        T: DeserializeOwned,
+    {
+        let text = self.fetch_text(self.client.get(url)).await?;
+        Self::parse_json(&text)
+    }
+
+    /// 提交请求体并将响应的 JSON 数据反序列化
+    pub async fn post_json<T, R>(&self, url: Url, body: T) -> EhResult<R>
+    where
+        T: Into<reqwest::Body>,
+        R: DeserializeOwned,
+    {
+        let text = self.fetch_text(self.client.post(url).body(body)).await?;
+        Self::parse_json(&text)
+    }
+
+    /// 发送请求并读取响应文本，配置了缓存时优先使用缓存
+    ///
+    /// 缓存过期但仍在 `stale_secs` 内时，先返回旧响应并在后台刷新。
+    pub(super) async fn fetch_text(&self, request: RequestBuilder) -> EhResult<String> {
+        let request = request.build()?;
+        let offline = self.cache_mode == EhCacheMode::Offline;
+        let cached = match &self.cache {
+            Some(cache) if self.cache_mode != EhCacheMode::Bypass => {
+                let identity = self
+                    .jar
+                    .get(request.url())
+                    .into_iter()
+                    .find(|(name, _)| name == "ipb_member_id")
+                    .map(|(_, value)| value);
+                EhCache::key(&request, &self.endpoints, identity.as_deref())
+                    .map(|(key, kind)| (cache.clone(), key, kind))
             }
+            _ => None,
         };
-        let text = match res.text().await {
-            Ok(text) => text,
-            Err(err) => {
-                return Err(format!("Error: {}", err));
+        let Some((cache, key, kind)) = cached else {
+            if offline {
+                return Err(EhError::CacheMiss(request.url().to_string()));
             }
+            return self.fetch_network(request).await;
         };
+        match cache.get(&key, kind) {
+            Some((body, EhCacheState::Fresh)) => return Ok(body),
+            Some((body, _)) if offline => return Ok(body),
+            Some((body, EhCacheState::Stale)) => {
+                self.revalidate(cache, key, &request);
+                return Ok(body);
+            }
+            None if offline => return Err(EhError::CacheMiss(request.url().to_string())),
+            _ => {}
+        }
+        let url = request.url().to_string();
+        let text = self.fetch_network(request).await?;
+        if let Err(err) = cache.put(&key, &url, &text) {
+            log::warn!("Failed to write cache for {}: {}", url, err);
+        }
         Ok(text)
     }
 
-    pub async fn get_json<T>(&self, url: Url) -> Result<T, String>
-    where
-        T: DeserializeOwned,
-    {
-        let res: Result<reqwest::Response, reqwest::Error> = self.client.get(url).send().await;
-        let res = match res {
-            Ok(res) => res,
-            Err(err) => {
-                return Err(format!("Error: {}", err));
+    /// 在后台重新请求并更新缓存
+    fn revalidate(&self, cache: Arc<EhCache>, key: String, request: &Request) {
+        let Some(request) = request.try_clone() else {
+            return;
+        };
+        if !cache.begin_revalidate(&key) {
+            return;
+        }
+        let client = self.with_cache_mode(EhCacheMode::Bypass);
+        tokio::spawn(async move {
+            let url = request.url().to_string();
+            match client.fetch_network(request).await {
+                Ok(text) => {
+                    if let Err(err) = cache.put(&key, &url, &text) {
+                        log::warn!("Failed to write cache for {}: {}", url, err);
+                    }
+                }
+                Err(err) => log::warn!("Failed to revalidate {}: {}", url, err),
             }
+            cache.end_revalidate(&key);
+        });
+    }
+
+    /// 通过网络发送请求并读取响应文本，按重试策略重试可恢复的错误
+    ///
+    /// 每个请求对应一个 `eh_request` span，记录请求类型、状态码、响应字节数、尝试次数与耗时。
+    async fn fetch_network(&self, request: Request) -> EhResult<String> {
+        let kind = request_kind(&self.endpoints, request.url());
+        let span = tracing::info_span!(
+            "eh_request",
+            method = %request.method(),
+            url = %request.url(),
+            kind,
+            status = field::Empty,
+            bytes = field::Empty,
+            attempts = field::Empty,
+            latency_ms = field::Empty,
+        );
+        let start = Instant::now();
+        let mut attempts = 1;
+        let fetch = self.fetch_retry(request, &mut attempts);
+        let result = match &self.cancel {
+            Some(token) => token.run(fetch).instrument(span.clone()).await,
+            None => fetch.instrument(span.clone()).await,
         };
-        let json = match res.json::<T>().await {
-            Ok(json) => json,
+        let elapsed = start.elapsed();
+        span.record("attempts", attempts);
+        span.record("latency_ms", elapsed.as_millis() as u64);
+        match &result {
+            Ok(text) => {
+                span.record("bytes", text.len());
+            }
             Err(err) => {
-                return Err(format!("Error: {}", err));
+                if let EhError::HttpStatus { status, .. } = err {
+                    span.record("status", status);
+                }
+                span.in_scope(|| tracing::debug!(error = %err, "request failed"));
             }
-        };
-        Ok(json)
+        }
+        if let Some(metrics) = &self.metrics {
+            metrics.record(kind, result.as_ref().map(String::len), attempts, elapsed);
+        }
+        result
+    }
+
+    /// 按重试策略发送请求，`attempt` 记录包含首次请求在内的尝试次数
+    async fn fetch_retry(&self, request: Request, attempt: &mut u32) -> EhResult<String> {
+        loop {
+            let Some(current) = request.try_clone() else {
+                return self.fetch_once(request).await;
+            };
+            match self.fetch_once(current).await {
+                Err(err) if *attempt < self.retry.max_attempts && self.retry.is_retryable(&err) => {
+                    tracing::warn!(attempt = *attempt, error = %err, "Request to {} failed, retrying", request.url());
+                    tokio::time::sleep(self.retry.delay(*attempt)).await;
+                    *attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// 发送一次请求并读取响应文本，由客户端处理 Cookie 与重定向
+    ///
+    /// 失败时依次调用中间件的 [`on_error`](EhMiddleware::on_error)。
+    async fn fetch_once(&self, request: Request) -> EhResult<String> {
+        let info = EhRequestInfo::from(&request);
+        let result = self.follow(request).await;
+        if let Err(EhError::Site(SiteError::IpBanned { expires_in })) = &result {
+            tracing::warn!(
+                url = %info.url,
+                expires_in = ?expires_in,
+                "IP banned"
+            );
+        }
+        if let Err(err) = &result {
+            for middleware in self.middlewares.iter() {
+                middleware.on_error(&info, err).await;
+            }
+        }
+        result
+    }
+
+    /// 发送请求并跟随重定向，每次跳转都按目标地址获取频率限制的令牌
+    async fn follow(&self, request: Request) -> EhResult<String> {
+        let mut request = request;
+        for (key, value) in self.headers.iter() {
+            if !request.headers().contains_key(key) {
+                request.headers_mut().insert(key, value.clone());
+            }
+        }
+        let mut redirects = 0;
+        loop {
+            let url = request.url().clone();
+            if let Some(limiter) = &self.limiter {
+                limiter.acquire(&url, self.priority).await;
+            }
+            if !request.headers().contains_key(COOKIE) {
+                if let Some(cookie) = self.jar.cookies(&url) {
+                    request.headers_mut().insert(COOKIE, cookie);
+                }
+            }
+            let method = request.method().clone();
+            let previous = request.try_clone();
+            let res = self.execute(request).await?;
+            Span::current().record("status", res.status);
+            let location = res
+                .get_header("location")
+                .filter(|_| (300..400).contains(&res.status))
+                .and_then(|location| url.join(location).ok());
+            let Some(location) = location else {
+                return Ok(res.body);
+            };
+            if redirects >= self.max_redirects {
+                return Err(EhError::HttpStatus {
+                    status: res.status,
+                    url: url.to_string(),
+                });
+            }
+            request = follow_redirect(previous, &method, res.status, location, &self.headers);
+            redirects += 1;
+        }
     }
 
-    pub async fn post_json<T, R>(&self, url: Url, body: T) -> Result<R, String>
+    /// 经过中间件通过传输层发送单个请求，并保存响应中的 Cookie
+    async fn execute(&self, mut request: Request) -> EhResult<EhResponse> {
+        for middleware in self.middlewares.iter() {
+            middleware.before_request(&mut request).await?;
+        }
+        let info = EhRequestInfo::from(&request);
+        let mut res = self.transport.execute(request).await?;
+        self.jar
+            .store_response(res.get_headers("set-cookie"), &info.url);
+        for middleware in self.middlewares.iter() {
+            middleware.after_response(&info, &mut res).await?;
+        }
+        Ok(res)
+    }
+
+    /// 将响应文本解析为 JSON
+    fn parse_json<R>(text: &str) -> EhResult<R>
     where
-        T: Into<reqwest::Body>,
         R: DeserializeOwned,
     {
-        let res = self.client.post(url).body(body).send().await;
-        let res = match res {
-            Ok(res) => res,
-            Err(err) => {
-                return Err(format!("Error: {}", err));
+        serde_json::from_str(text).map_err(|err| EhError::parse("json", err.to_string()))
+    }
+}
+
+/// 根据重定向响应生成下一个请求
+///
+/// 307 与 308 保留原请求方法与请求体，303 以及 POST 请求的 301、302 改为不带请求体的 GET。
+/// 重定向到其他站点时移除身份验证请求头与配置的请求头 `configured`。
+fn follow_redirect(
+    previous: Option<Request>,
+    method: &Method,
+    status: u16,
+    url: Url,
+    configured: &HeaderMap,
+) -> Request {
+    let previous = previous.map(|mut request| {
+        if request.url().origin() != url.origin() {
+            let headers = request.headers_mut();
+            headers.remove(AUTHORIZATION);
+            headers.remove(PROXY_AUTHORIZATION);
+            for key in configured.keys() {
+                headers.remove(key);
             }
-        };
-        let json = match res.json::<R>().await {
-            Ok(json) => json,
-            Err(err) => {
-                return Err(format!("Error: {}", err));
+        }
+        request
+    });
+    let previous = match (previous, status) {
+        (Some(mut request), 307 | 308) => {
+            *request.url_mut() = url;
+            request.headers_mut().remove(COOKIE);
+            return request;
+        }
+        (previous, _) => previous,
+    };
+    let method = if status == 303 || (*method == Method::POST && matches!(status, 301 | 302)) {
+        Method::GET
+    } else {
+        method.clone()
+    };
+    let mut request = Request::new(method, url);
+    if let Some(previous) = previous {
+        for (key, value) in previous.headers() {
+            if key != COOKIE && key != CONTENT_TYPE && key != CONTENT_LENGTH {
+                request.headers_mut().append(key, value.clone());
             }
-        };
-        Ok(json)
+        }
     }
+    request
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
-        client::{config::EhClientConfig, proxy::EhClientProxy},
+        client::{
+            config::EhClientConfig,
+            pool::EhProxyPoolConfig,
+            proxy::{EhClientProxy, EhProxyRule},
+            resolve::EhResolveConfig,
+        },
         dto::{keyword::Keyword, site::Site},
+        url::search::SearchBuilder,
     };
 
     use super::EhClient;
+    use crate::{
+        client::{
+            endpoints::EhEndpoints,
+            http::EhHttpConfig,
+            limiter::{EhRateLimit, EhRateLimitConfig},
+            retry::EhRetryPolicy,
+            test::{TestResponse, TestServer},
+            transport::{EhResponse, FixtureTransport},
+        },
+        error::{EhError, SiteError},
+    };
+    use reqwest::Url;
+    use std::{sync::Arc, time::Duration};
+    use tokio::time::Instant;
     use tokio::{fs::File, io::AsyncWriteExt};
 
     #[tokio::test]
+    #[ignore = "需要通过本地代理访问站点"]
     async fn test_eh_client() {
         let proxy = if dotenvy::dotenv().is_ok() {
             EhClientProxy::env()
@@ -142,8 +593,9 @@ mod tests {
         };
         let config = EhClientConfig {
             site: Site::Eh,
-            proxy: proxy,
+            proxy,
             auth: None,
+            ..Default::default()
         };
         let client = EhClient::new(config);
         let res = client
@@ -172,4 +624,352 @@ mod tests {
             Err(err) => panic!("Error: {}", err),
         }
     }
+
+    #[tokio::test]
+    async fn test_eh_client_fixture() {
+        let keywords = vec![
+            Keyword::Artist("simon".into()),
+            Keyword::Language("chinese".into()),
+        ];
+        let url = SearchBuilder::new(Site::Eh)
+            .add_keywords(keywords.clone())
+            .build()
+            .unwrap();
+        let transport = Arc::new(
+            FixtureTransport::new().get(url.as_str(), EhResponse::new(200, "<html>search</html>")),
+        );
+        let client = EhClient::new_with_transport(EhClientConfig::default(), transport.clone());
+        let text = client.search(keywords, None).await.unwrap();
+        assert_eq!(text, "<html>search</html>");
+        assert_eq!(transport.requests()[0].url, url.as_str());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_limit_redirect() {
+        let transport = Arc::new(
+            FixtureTransport::new()
+                .get(
+                    "https://e-hentai.org/old",
+                    EhResponse::new(302, "").header("Location", "/new"),
+                )
+                .get("https://e-hentai.org/new", EhResponse::new(200, "new")),
+        );
+        let config = EhClientConfig {
+            rate_limit: Some(EhRateLimitConfig {
+                site: EhRateLimit::new(1, 1000, 1),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let client = EhClient::new_with_transport(config, transport);
+        let start = Instant::now();
+        let url = Url::parse("https://e-hentai.org/old").unwrap();
+        assert_eq!(client.get_html(url).await.unwrap(), "new");
+        assert!(start.elapsed() >= Duration::from_millis(1000));
+    }
+
+    #[tokio::test]
+    async fn test_retry_transient_status() {
+        let server = TestServer::start(vec![(
+            "/",
+            vec![
+                TestResponse::new(503, "Service Unavailable"),
+                TestResponse::new(502, "Bad Gateway"),
+                TestResponse::new(200, "<html>ok</html>"),
+            ],
+        )])
+        .await;
+        let retry = EhRetryPolicy {
+            base_delay_ms: 1,
+            ..Default::default()
+        };
+        let client = EhClient::new(EhClientConfig::default()).with_retry(retry);
+        let text = client.get_html(server.url("/")).await.unwrap();
+        assert_eq!(text, "<html>ok</html>");
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_retry_gives_up() {
+        let server =
+            TestServer::start(vec![("/", vec![TestResponse::new(503, "Unavailable")])]).await;
+        let retry = EhRetryPolicy {
+            max_attempts: 2,
+            base_delay_ms: 1,
+            ..Default::default()
+        };
+        let client = EhClient::new(EhClientConfig::default()).with_retry(retry);
+        let result = client.get_html(server.url("/")).await;
+        assert!(matches!(
+            result,
+            Err(EhError::HttpStatus { status: 503, .. })
+        ));
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_retry_skips_ban() {
+        let server = TestServer::start(vec![(
+            "/",
+            vec![TestResponse::new(
+                200,
+                "Your IP address has been temporarily banned for excessive pageloads. The ban expires in 1 hour",
+            )],
+        )])
+        .await;
+        let retry = EhRetryPolicy {
+            base_delay_ms: 1,
+            ..Default::default()
+        };
+        let client = EhClient::new(EhClientConfig::default()).with_retry(retry);
+        let result = client.get_html(server.url("/")).await;
+        assert!(matches!(
+            result,
+            Err(EhError::Site(SiteError::IpBanned { .. }))
+        ));
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_custom_endpoints() {
+        let server = TestServer::start(vec![(
+            "/",
+            vec![TestResponse::new(200, "<html>mirror</html>")],
+        )])
+        .await;
+        let config = EhClientConfig {
+            endpoints: EhEndpoints {
+                e_hentai: server.url("/mirror/").to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let client = EhClient::new(config);
+        let text = client
+            .search(vec![Keyword::Artist("simon".into())], None)
+            .await
+            .unwrap();
+        assert_eq!(text, "<html>mirror</html>");
+        let request = &server.requests()[0];
+        assert!(request.path.starts_with("/mirror/?f_search="));
+
+        let config = EhClientConfig {
+            site: Site::Un,
+            ..Default::default()
+        };
+        let result = EhClient::new(config).search(vec![], None).await;
+        assert!(matches!(result, Err(EhError::InvalidInput(_))));
+    }
+
+    #[tokio::test]
+    async fn test_proxy_routing() {
+        let proxy_server = TestServer::start(vec![(
+            "http://example.invalid/",
+            vec![TestResponse::new(200, "<html>proxied</html>")],
+        )])
+        .await;
+        let direct = TestServer::start(vec![("/", vec![TestResponse::new(200, "direct")])]).await;
+        let proxy = EhClientProxy::new("http", "127.0.0.1", proxy_server.addr.port())
+            .auth("user", "secret");
+        let config = EhClientConfig {
+            proxy_rules: vec![EhProxyRule::new(
+                vec!["example.invalid".into()],
+                Some(proxy),
+            )],
+            ..Default::default()
+        };
+        let client = EhClient::try_new(config).unwrap();
+        let url = Url::parse("http://example.invalid/page").unwrap();
+        assert_eq!(client.get_html(url).await.unwrap(), "<html>proxied</html>");
+        let request = &proxy_server.requests()[0];
+        assert_eq!(request.path, "http://example.invalid/page");
+        assert_eq!(
+            request.header("proxy-authorization"),
+            Some("Basic dXNlcjpzZWNyZXQ=")
+        );
+        assert_eq!(client.get_html(direct.url("/")).await.unwrap(), "direct");
+        assert_eq!(proxy_server.requests().len(), 1);
+
+        let config = EhClientConfig {
+            proxy: Some(EhClientProxy::new("ftp", "127.0.0.1", 21)),
+            ..Default::default()
+        };
+        assert!(matches!(
+            EhClient::try_new(config),
+            Err(EhError::InvalidInput(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_proxy_pool() {
+        let banned = TestServer::start(vec![(
+            "http://example.invalid/",
+            vec![TestResponse::new(
+                200,
+                "Your IP address has been temporarily banned for excessive pageloads.",
+            )],
+        )])
+        .await;
+        let healthy = TestServer::start(vec![(
+            "http://example.invalid/",
+            vec![TestResponse::new(200, "ok")],
+        )])
+        .await;
+        let proxies = [&banned, &healthy]
+            .iter()
+            .map(|server| EhClientProxy::new("http", "127.0.0.1", server.addr.port()))
+            .collect();
+        let config = EhClientConfig {
+            proxy_pool: Some(EhProxyPoolConfig::new(proxies)),
+            ..Default::default()
+        };
+        let client = EhClient::try_new(config).unwrap();
+        let url = Url::parse("http://example.invalid/page").unwrap();
+        for _ in 0..3 {
+            assert_eq!(client.get_html(url.clone()).await.unwrap(), "ok");
+        }
+        assert_eq!(banned.requests().len(), 1);
+        assert_eq!(healthy.requests().len(), 3);
+        let status = client.proxy_pool().unwrap().status();
+        assert!(!status[0].available);
+        assert_eq!(status[0].bans, 1);
+        assert!(status[1].available);
+
+        let proxy = EhClientProxy::new("http", "127.0.0.1", banned.addr.port());
+        let config = EhClientConfig {
+            proxy_pool: Some(EhProxyPoolConfig::new(vec![proxy])),
+            ..Default::default()
+        };
+        let client = EhClient::try_new(config).unwrap();
+        assert!(matches!(
+            client.get_html(url.clone()).await,
+            Err(EhError::Site(SiteError::IpBanned { .. }))
+        ));
+        let result = client.get_html(url).await;
+        assert!(matches!(
+            result,
+            Err(EhError::ProxyPoolExhausted {
+                cooldown_remaining: Some(_)
+            })
+        ));
+        assert!(result.unwrap_err().is_restricted());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_override() {
+        let server = TestServer::start(vec![("/", vec![TestResponse::new(200, "resolved")])]).await;
+        let resolve = EhResolveConfig::new().host("example.invalid", vec![server.addr.ip()]);
+        let config = EhClientConfig {
+            resolve: Some(resolve),
+            ..Default::default()
+        };
+        let client = EhClient::try_new(config).unwrap();
+        let host = format!("example.invalid:{}", server.addr.port());
+        let url = Url::parse(&format!("http://{}/page", host)).unwrap();
+        assert_eq!(client.get_html(url).await.unwrap(), "resolved");
+        assert_eq!(server.requests()[0].header("host"), Some(host.as_str()));
+    }
+
+    #[tokio::test]
+    async fn test_http_settings() {
+        let server = TestServer::start(vec![
+            (
+                "/redirect",
+                vec![TestResponse::new(302, "").header("Location", "/target")],
+            ),
+            ("/target", vec![TestResponse::new(200, "target")]),
+        ])
+        .await;
+        let mut http = EhHttpConfig {
+            user_agent: "libeh-test".into(),
+            max_redirects: 0,
+            ..Default::default()
+        };
+        http.headers
+            .insert("Accept-Language".into(), "en-US".into());
+        let config = EhClientConfig {
+            http,
+            ..Default::default()
+        };
+        let client = EhClient::try_new(config).unwrap();
+        let result = client.get_html(server.url("/redirect")).await;
+        assert!(matches!(
+            result,
+            Err(EhError::HttpStatus { status: 302, .. })
+        ));
+        let request = &server.requests()[0];
+        assert_eq!(request.header("user-agent"), Some("libeh-test"));
+        assert_eq!(request.header("accept-language"), Some("en-US"));
+
+        // 接受连接但从不响应的服务器
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut streams = vec![];
+            while let Ok((stream, _)) = listener.accept().await {
+                streams.push(stream);
+            }
+        });
+        let config = EhClientConfig {
+            http: EhHttpConfig {
+                read_timeout_ms: Some(100),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let client = EhClient::try_new(config).unwrap();
+        let url = Url::parse(&format!("http://{}/", addr)).unwrap();
+        let result = client.get_html(url).await;
+        assert!(
+            matches!(result, Err(EhError::Io(err)) if err.kind() == std::io::ErrorKind::TimedOut)
+        );
+
+        let mut http = EhHttpConfig::default();
+        http.headers.insert("Bad Header".into(), "x".into());
+        let config = EhClientConfig {
+            http,
+            ..Default::default()
+        };
+        assert!(matches!(
+            EhClient::try_new(config),
+            Err(EhError::InvalidInput(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_redirect_drops_headers() {
+        let other =
+            TestServer::start(vec![("/target", vec![TestResponse::new(200, "target")])]).await;
+        let location = other.url("/target").to_string();
+        let server = TestServer::start(vec![
+            (
+                "/redirect",
+                vec![TestResponse::new(302, "").header("Location", "/same")],
+            ),
+            (
+                "/same",
+                vec![TestResponse::new(307, "").header("Location", &location)],
+            ),
+        ])
+        .await;
+        let mut http = EhHttpConfig::default();
+        http.headers
+            .insert("Authorization".into(), "Bearer secret".into());
+        http.headers.insert("X-Token".into(), "secret".into());
+        let config = EhClientConfig {
+            http,
+            ..Default::default()
+        };
+        let client = EhClient::try_new(config).unwrap();
+        let text = client.get_html(server.url("/redirect")).await.unwrap();
+        assert_eq!(text, "target");
+        for request in server.requests() {
+            assert_eq!(request.header("authorization"), Some("Bearer secret"));
+            assert_eq!(request.header("x-token"), Some("secret"));
+        }
+        let request = &other.requests()[0];
+        assert_eq!(request.header("authorization"), None);
+        assert_eq!(request.header("x-token"), None);
+        assert!(request.header("user-agent").is_some());
+    }
 }
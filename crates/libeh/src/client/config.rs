@@ -1,32 +1,147 @@
 use serde::{Deserialize, Serialize};
 use std::env;
 
-use crate::dto::site::Site;
+use crate::{
+    dto::site::Site,
+    error::{EhError, EhResult},
+};
 
-use super::{auth::EhClientAuth, proxy::EhClientProxy};
+use super::{
+    auth::EhClientAuth,
+    cache::EhCacheConfig,
+    cookie::EhCookieStoreConfig,
+    credentials::EhCredentialsConfig,
+    endpoints::EhEndpoints,
+    http::EhHttpConfig,
+    limiter::EhRateLimitConfig,
+    pool::EhProxyPoolConfig,
+    proxy::{EhClientProxy, EhProxyRule},
+    resolve::EhResolveConfig,
+    retry::EhRetryPolicy,
+};
 
 /// E-Hentai/ExHentai 客户端配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EhClientConfig {
     /// 站点类型
     pub site: Site,
+    /// 站点各服务的地址，默认为官方地址
+    #[serde(default)]
+    pub endpoints: EhEndpoints,
+    /// 超时、User-Agent、请求头与重定向等 HTTP 连接设置
+    #[serde(default)]
+    pub http: EhHttpConfig,
     /// 代理设置，默认为 None
     pub proxy: Option<EhClientProxy>,
+    /// 按主机选择代理的规则，按顺序匹配，没有匹配的规则时使用 `proxy`
+    #[serde(default)]
+    pub proxy_rules: Vec<EhProxyRule>,
+    /// 代理池设置，默认为 None，不能与 `proxy` 同时设置
+    #[serde(default)]
+    pub proxy_pool: Option<EhProxyPoolConfig>,
+    /// 域名解析设置，默认为 None，即使用系统解析
+    #[serde(default)]
+    pub resolve: Option<EhResolveConfig>,
     /// 用户身份验证设置，默认为 None
     pub auth: Option<EhClientAuth>,
+    /// 加密凭据文件设置，默认为 None，不能与 `auth` 同时设置
+    #[serde(default)]
+    pub credentials: Option<EhCredentialsConfig>,
+    /// 请求频率限制设置，默认为 None，即不限制
+    #[serde(default)]
+    pub rate_limit: Option<EhRateLimitConfig>,
+    /// 请求失败时的重试策略，默认为 None，即不重试
+    #[serde(default)]
+    pub retry: Option<EhRetryPolicy>,
+    /// Cookie 持久化设置，默认为 None，即 Cookie 仅保存在内存中
+    #[serde(default)]
+    pub cookie_store: Option<EhCookieStoreConfig>,
+    /// 响应缓存设置，默认为 None，即不缓存
+    #[serde(default)]
+    pub cache: Option<EhCacheConfig>,
+    /// 是否统计请求指标，默认为 false，启用后可通过 [`EhClient::metrics`](super::client::EhClient::metrics) 导出
+    #[serde(default)]
+    pub metrics: bool,
 }
 
 impl EhClientConfig {
-    /// 从环境变量中读取配置
+    /// 从环境变量中读取配置，忽略无效的代理设置
     pub fn env() -> Self {
         let site = env::var("EH_SITE").unwrap_or("e-hentai.org".to_owned());
         let site = Site::from(site);
         EhClientConfig {
             site,
+            endpoints: EhEndpoints::default(),
+            http: EhHttpConfig::default(),
             proxy: EhClientProxy::env(),
+            proxy_rules: EhProxyRule::env().unwrap_or_else(|err| {
+                log::warn!("Ignoring proxy rules from environment: {}", err);
+                vec![]
+            }),
+            proxy_pool: None,
+            resolve: None,
             auth: EhClientAuth::env(),
+            credentials: None,
+            rate_limit: None,
+            retry: None,
+            cookie_store: None,
+            cache: None,
+            metrics: false,
         }
     }
+
+    /// 检查配置是否有效，包括站点地址、代理、连接与域名解析设置
+    pub fn validate(&self) -> EhResult<()> {
+        self.endpoints.validate()?;
+        self.http.validate()?;
+        let proxies = self.proxy.iter().chain(
+            self.proxy_rules
+                .iter()
+                .filter_map(|rule| rule.proxy.as_ref()),
+        );
+        for proxy in proxies {
+            proxy.validate()?;
+        }
+        if let Some(pool) = &self.proxy_pool {
+            if self.proxy.is_some() {
+                return Err(EhError::invalid_input(
+                    "proxy and proxy_pool cannot be set at the same time.",
+                ));
+            }
+            pool.validate()?;
+        }
+        if let Some(resolve) = &self.resolve {
+            resolve.validate()?;
+        }
+        if let Some(store) = &self.cookie_store {
+            store.validate(self.auth.as_ref())?;
+        }
+        if self.auth.is_some() && self.credentials.is_some() {
+            return Err(EhError::invalid_input(
+                "auth and credentials cannot be set at the same time.",
+            ));
+        }
+        Ok(())
+    }
+
+    /// 解密凭据文件并填入 `auth`，未设置凭据文件时不做任何修改
+    pub fn load_credentials(mut self) -> EhResult<Self> {
+        if let Some(credentials) = &self.credentials {
+            if self.auth.is_none() {
+                self.auth = Some(credentials.load()?);
+            }
+        }
+        Ok(self)
+    }
+
+    /// 从环境变量中读取配置，代理设置无效时返回错误
+    pub fn try_env() -> EhResult<Self> {
+        Ok(EhClientConfig {
+            proxy: EhClientProxy::try_env()?,
+            proxy_rules: EhProxyRule::env()?,
+            ..Self::env()
+        })
+    }
 }
 
 impl Default for EhClientConfig {
@@ -34,8 +149,19 @@ impl Default for EhClientConfig {
     fn default() -> Self {
         EhClientConfig {
             site: Site::Eh,
+            endpoints: EhEndpoints::default(),
+            http: EhHttpConfig::default(),
             proxy: None,
+            proxy_rules: vec![],
+            proxy_pool: None,
+            resolve: None,
             auth: None,
+            credentials: None,
+            rate_limit: None,
+            retry: None,
+            cookie_store: None,
+            cache: None,
+            metrics: false,
         }
     }
 }
@@ -99,6 +225,35 @@ proxy:
         println!("{:?}", result.unwrap());
     }
 
+    #[test]
+    fn config_partial_policies() {
+        use crate::client::{
+            config::EhClientConfig, limiter::EhRateLimitConfig, retry::EhRetryPolicy,
+        };
+        let config = r#"
+site: eh
+retry:
+  max_attempts: 5
+rate_limit:
+  api:
+    requests: 2
+    interval_ms: 1000
+    burst: 2
+"#;
+        let config = serde_yaml::from_str::<EhClientConfig>(config).unwrap();
+        let retry = config.retry.unwrap();
+        assert_eq!(retry.max_attempts, 5);
+        assert_eq!(
+            retry.retry_statuses,
+            EhRetryPolicy::default().retry_statuses
+        );
+        let rate_limit = config.rate_limit.unwrap();
+        assert_eq!(rate_limit.api.requests, 2);
+        assert_eq!(rate_limit.site, EhRateLimitConfig::default().site);
+        let config = serde_yaml::from_str::<EhClientConfig>("site: eh\nrate_limit: {}").unwrap();
+        assert_eq!(config.rate_limit, Some(EhRateLimitConfig::default()));
+    }
+
     #[test]
     fn config_from_env() {
         use crate::client::config::EhClientConfig;
@@ -0,0 +1,307 @@
+use std::{
+    fs::{self, File},
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+    sync::RwLock,
+};
+
+use cookie_store::{CookieStore, RawCookie};
+use reqwest::{header::HeaderValue, Url};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{EhError, EhResult};
+
+use super::auth::EhClientAuth;
+
+/// Cookie 持久化设置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EhCookieStoreConfig {
+    /// 存放 Cookie 文件的目录
+    pub dir: PathBuf,
+    /// 账号名称，用作 Cookie 文件名；为 None 时使用用户 ID，未登录时为 "anonymous"
+    #[serde(default)]
+    pub account: Option<String>,
+}
+
+impl EhCookieStoreConfig {
+    /// 创建一个新的 EhCookieStoreConfig 实例
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        EhCookieStoreConfig {
+            dir: dir.into(),
+            account: None,
+        }
+    }
+
+    /// 检查账号名称能否用作文件名，不能为空或包含路径分隔符、`..`
+    pub fn validate(&self, auth: Option<&EhClientAuth>) -> EhResult<()> {
+        let account = match (&self.account, auth) {
+            (Some(account), _) => account,
+            (None, Some(auth)) => &auth.ipb_member_id,
+            (None, None) => return Ok(()),
+        };
+        if !is_valid_account_name(account) {
+            return Err(EhError::invalid_input(format!(
+                "Invalid cookie store account: {}",
+                account
+            )));
+        }
+        Ok(())
+    }
+
+    /// 获取账号对应的 Cookie 文件路径
+    pub fn path(&self, auth: Option<&EhClientAuth>) -> PathBuf {
+        let account = match (&self.account, auth) {
+            (Some(account), _) => account.clone(),
+            (None, Some(auth)) => auth.ipb_member_id.clone(),
+            (None, None) => "anonymous".to_string(),
+        };
+        self.dir.join(format!("{}.json", account))
+    }
+}
+
+/// 账号名称能否用作文件或目录名，不能为空或包含路径分隔符、`..`
+pub(super) fn is_valid_account_name(account: &str) -> bool {
+    !(account.is_empty()
+        || account.contains(['/', '\\'])
+        || account == "."
+        || account.contains(".."))
+}
+
+/// EhClient 使用的 Cookie 容器，可选地在 Cookie 变化时保存到磁盘
+pub struct EhCookieJar {
+    store: RwLock<CookieStore>,
+    path: Option<PathBuf>,
+    /// 不保存到文件的 Cookie 名称
+    skipped: Vec<String>,
+}
+
+impl EhCookieJar {
+    /// 创建一个仅保存在内存中的 Cookie 容器
+    pub fn new() -> Self {
+        EhCookieJar {
+            store: RwLock::new(CookieStore::default()),
+            path: None,
+            skipped: vec![],
+        }
+    }
+
+    /// 从文件加载 Cookie 容器，文件不存在时创建空容器，之后的变化会保存到该文件
+    pub fn load(path: impl AsRef<Path>) -> EhResult<Self> {
+        let path = path.as_ref().to_path_buf();
+        let store = if path.exists() {
+            let reader = BufReader::new(File::open(&path)?);
+            cookie_store::serde::json::load_all(reader)
+                .map_err(|err| EhError::parse("cookie store", err.to_string()))?
+        } else {
+            CookieStore::default()
+        };
+        Ok(EhCookieJar {
+            store: RwLock::new(store),
+            path: Some(path),
+            skipped: vec![],
+        })
+    }
+
+    /// 设置不保存到文件的 Cookie 名称，这些 Cookie 只保留在内存中
+    pub fn skip_persisting(mut self, names: &[&str]) -> Self {
+        self.skipped = names.iter().map(|name| name.to_string()).collect();
+        self
+    }
+
+    /// 添加一个 Cookie，格式与 `Set-Cookie` 响应头相同
+    pub fn add_cookie_str(&self, cookie: &str, url: &Url) {
+        if let Ok(cookie) = RawCookie::parse(cookie) {
+            let mut store = self.store.write().unwrap();
+            store.store_response_cookies(std::iter::once(cookie.into_owned()), url);
+        }
+    }
+
+    /// 保存响应中的 `Set-Cookie`，Cookie 有变化且指定了文件时写入磁盘
+    pub fn store_response<'a>(&self, set_cookies: impl Iterator<Item = &'a str>, url: &Url) {
+        let cookies: Vec<RawCookie<'static>> = set_cookies
+            .filter_map(|value| RawCookie::parse(value.to_string()).ok())
+            .collect();
+        if cookies.is_empty() {
+            return;
+        }
+        self.store
+            .write()
+            .unwrap()
+            .store_response_cookies(cookies.into_iter(), url);
+        if let Err(err) = self.save() {
+            log::warn!("Failed to save cookies: {}", err);
+        }
+    }
+
+    /// 获取发往指定 URL 的 Cookie 键值对
+    pub fn get(&self, url: &Url) -> Vec<(String, String)> {
+        let store = self.store.read().unwrap();
+        store
+            .get_request_values(url)
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    /// 将 Cookie 保存到加载时的文件，未指定文件时不做任何操作
+    pub fn save(&self) -> EhResult<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        {
+            let mut writer = BufWriter::new(File::create(&tmp)?);
+            let store = self.store.read().unwrap();
+            let cookies = store
+                .iter_any()
+                .filter(|cookie| !self.skipped.iter().any(|name| name == cookie.name()))
+                .map(|cookie| Ok::<_, EhError>(cookie.clone()));
+            let store = CookieStore::from_cookies(cookies, true)?;
+            cookie_store::serde::json::save_incl_expired_and_nonpersistent(&store, &mut writer)
+                .map_err(|err| EhError::parse("cookie store", err.to_string()))?;
+        }
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+}
+
+impl Default for EhCookieJar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl reqwest::cookie::CookieStore for EhCookieJar {
+    fn set_cookies(&self, cookie_headers: &mut dyn Iterator<Item = &HeaderValue>, url: &Url) {
+        self.store_response(cookie_headers.filter_map(|value| value.to_str().ok()), url);
+    }
+
+    fn cookies(&self, url: &Url) -> Option<HeaderValue> {
+        let header = self
+            .get(url)
+            .into_iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join("; ");
+        if header.is_empty() {
+            return None;
+        }
+        HeaderValue::from_str(&header).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use reqwest::{cookie::CookieStore, header::HeaderValue, Url};
+
+    use crate::client::{
+        auth::EhClientAuth,
+        client::EhClient,
+        config::EhClientConfig,
+        test::{TestResponse, TestServer},
+        transport::FixtureTransport,
+    };
+
+    use super::{EhCookieJar, EhCookieStoreConfig};
+
+    #[test]
+    fn test_cookie_store_path() {
+        let config = EhCookieStoreConfig::new("/tmp/cookies");
+        assert_eq!(
+            config.path(None).to_str(),
+            Some("/tmp/cookies/anonymous.json")
+        );
+        let auth = EhClientAuth::new("123456", "abcdef", None);
+        assert_eq!(
+            config.path(Some(&auth)).to_str(),
+            Some("/tmp/cookies/123456.json")
+        );
+    }
+
+    #[test]
+    fn test_cookie_store_validate() {
+        let mut config = EhCookieStoreConfig::new("/tmp/cookies");
+        assert!(config.validate(None).is_ok());
+        for account in ["main", "main.backup"] {
+            config.account = Some(account.into());
+            assert!(config.validate(None).is_ok());
+        }
+        for account in ["", "../x", "a/b", "a\\b", "..", "."] {
+            config.account = Some(account.into());
+            assert!(config.validate(None).is_err(), "{}", account);
+        }
+        let config = EhCookieStoreConfig::new("/tmp/cookies");
+        let auth = EhClientAuth::new("../123456", "abcdef", None);
+        assert!(config.validate(Some(&auth)).is_err());
+    }
+
+    #[test]
+    fn test_cookie_store_persist() {
+        let dir = std::env::temp_dir().join(format!("libeh-cookie-{}", std::process::id()));
+        let path = dir.join("account.json");
+        let url = Url::parse("https://e-hentai.org/").unwrap();
+        {
+            let jar = EhCookieJar::load(&path).unwrap();
+            let header = HeaderValue::from_static("sk=abcdef; Domain=e-hentai.org; Path=/");
+            jar.set_cookies(&mut std::iter::once(&header), &url);
+        }
+        assert!(path.exists());
+        let jar = EhCookieJar::load(&path).unwrap();
+        assert_eq!(
+            jar.cookies(&url),
+            Some(HeaderValue::from_static("sk=abcdef"))
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_client_cookie_store() {
+        let server = TestServer::start(vec![(
+            "/",
+            vec![TestResponse::new(200, "<html></html>").header("Set-Cookie", "sk=abcdef; path=/")],
+        )])
+        .await;
+        let dir = std::env::temp_dir().join(format!("libeh-client-cookie-{}", std::process::id()));
+        let config = EhClientConfig {
+            cookie_store: Some(EhCookieStoreConfig {
+                dir: dir.clone(),
+                account: Some("main".into()),
+            }),
+            ..Default::default()
+        };
+        let client = EhClient::new(config.clone());
+        client.get_html(server.url("/")).await.unwrap();
+        assert!(dir.join("main.json").exists());
+
+        let client = EhClient::new(config);
+        client.get_html(server.url("/")).await.unwrap();
+        assert_eq!(server.requests()[1].header("cookie"), Some("sk=abcdef"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_configured_auth_overrides_saved() {
+        let dir = std::env::temp_dir().join(format!("libeh-auth-cookie-{}", std::process::id()));
+        let url = Url::parse("https://e-hentai.org/").unwrap();
+        let jar = EhCookieJar::load(dir.join("main.json")).unwrap();
+        let header = HeaderValue::from_static("ipb_pass_hash=old; Domain=e-hentai.org; Path=/");
+        jar.set_cookies(&mut std::iter::once(&header), &url);
+        let config = EhClientConfig {
+            auth: Some(EhClientAuth::new("123456", "new", None)),
+            cookie_store: Some(EhCookieStoreConfig {
+                dir: dir.clone(),
+                account: Some("main".into()),
+            }),
+            ..Default::default()
+        };
+        let client = EhClient::new_with_transport(config, Arc::new(FixtureTransport::new()));
+        let cookies = client.cookies(&url);
+        assert!(cookies.contains(&("ipb_pass_hash".into(), "new".into())));
+        assert!(!cookies.contains(&("ipb_pass_hash".into(), "old".into())));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,289 @@
+use std::{
+    env, fmt,
+    fs::{self, File},
+    io::BufWriter,
+    path::{Path, PathBuf},
+};
+
+use argon2::Argon2;
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    Key, XChaCha20Poly1305, XNonce,
+};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{EhError, EhResult};
+
+use super::auth::EhClientAuth;
+
+/// 加密文件的格式版本
+const VERSION: u32 = 1;
+/// 密钥派生使用的盐长度
+const SALT_LEN: usize = 16;
+/// XChaCha20-Poly1305 的随机数长度
+const NONCE_LEN: usize = 24;
+
+/// 解密凭据文件使用的密钥来源
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub enum EhCredentialKey {
+    /// 从指定的环境变量读取口令
+    #[serde(rename = "passphrase_env")]
+    PassphraseEnv(String),
+    /// 使用密钥文件的全部内容作为口令
+    #[serde(rename = "key_file")]
+    KeyFile(PathBuf),
+    /// 直接提供的口令，不会被序列化
+    #[serde(skip)]
+    Passphrase(String),
+}
+
+impl EhCredentialKey {
+    /// 读取口令
+    fn secret(&self) -> EhResult<Vec<u8>> {
+        let secret = match self {
+            EhCredentialKey::PassphraseEnv(name) => env::var(name)
+                .map_err(|_| {
+                    EhError::invalid_input(format!("Passphrase variable {} is not set.", name))
+                })?
+                .into_bytes(),
+            EhCredentialKey::KeyFile(path) => fs::read(path)?,
+            EhCredentialKey::Passphrase(passphrase) => passphrase.as_bytes().to_vec(),
+        };
+        if secret.is_empty() {
+            return Err(EhError::invalid_input("Credential passphrase is empty."));
+        }
+        Ok(secret)
+    }
+}
+
+impl fmt::Debug for EhCredentialKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EhCredentialKey::PassphraseEnv(name) => {
+                f.debug_tuple("PassphraseEnv").field(name).finish()
+            }
+            EhCredentialKey::KeyFile(path) => f.debug_tuple("KeyFile").field(path).finish(),
+            EhCredentialKey::Passphrase(_) => f.debug_tuple("Passphrase").field(&"***").finish(),
+        }
+    }
+}
+
+/// 加密凭据文件设置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EhCredentialsConfig {
+    /// 凭据文件路径
+    pub path: PathBuf,
+    /// 密钥来源
+    pub key: EhCredentialKey,
+}
+
+impl EhCredentialsConfig {
+    /// 创建一个新的 EhCredentialsConfig 实例
+    pub fn new(path: impl AsRef<Path>, key: EhCredentialKey) -> Self {
+        EhCredentialsConfig {
+            path: path.as_ref().to_path_buf(),
+            key,
+        }
+    }
+
+    /// 读取并解密凭据文件
+    pub fn load(&self) -> EhResult<EhClientAuth> {
+        let content = fs::read_to_string(&self.path)?;
+        let file: CredentialsFile = serde_json::from_str(&content)
+            .map_err(|err| EhError::parse("credentials", err.to_string()))?;
+        file.decrypt(&self.key.secret()?)
+    }
+
+    /// 加密并写入凭据文件，每次写入使用新的盐与随机数
+    pub fn save(&self, auth: &EhClientAuth) -> EhResult<()> {
+        let file = CredentialsFile::encrypt(auth, &self.key.secret()?)?;
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let tmp = self.path.with_extension("tmp");
+        {
+            let writer = BufWriter::new(File::create(&tmp)?);
+            serde_json::to_writer_pretty(writer, &file)
+                .map_err(|err| EhError::parse("credentials", err.to_string()))?;
+        }
+        fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+/// 凭据文件的内容，以 Argon2id 从口令派生密钥，以 XChaCha20-Poly1305 加密
+#[derive(Debug, Serialize, Deserialize)]
+struct CredentialsFile {
+    version: u32,
+    /// 十六进制编码的盐
+    salt: String,
+    /// 十六进制编码的随机数
+    nonce: String,
+    /// 十六进制编码的密文
+    ciphertext: String,
+}
+
+impl CredentialsFile {
+    fn encrypt(auth: &EhClientAuth, secret: &[u8]) -> EhResult<Self> {
+        let mut salt = [0u8; SALT_LEN];
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::thread_rng().fill_bytes(&mut salt);
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let plaintext = serde_json::to_vec(auth)
+            .map_err(|err| EhError::parse("credentials", err.to_string()))?;
+        let ciphertext = cipher(secret, &salt)?
+            .encrypt(XNonce::from_slice(&nonce), plaintext.as_slice())
+            .map_err(|_| EhError::invalid_input("Failed to encrypt credentials."))?;
+        Ok(CredentialsFile {
+            version: VERSION,
+            salt: to_hex(&salt),
+            nonce: to_hex(&nonce),
+            ciphertext: to_hex(&ciphertext),
+        })
+    }
+
+    fn decrypt(&self, secret: &[u8]) -> EhResult<EhClientAuth> {
+        if self.version != VERSION {
+            return Err(EhError::parse(
+                "credentials",
+                format!("Unsupported version {}", self.version),
+            ));
+        }
+        let salt = from_hex(&self.salt)?;
+        let nonce = from_hex(&self.nonce)?;
+        if nonce.len() != NONCE_LEN {
+            return Err(EhError::parse("credentials", "Invalid nonce length."));
+        }
+        let plaintext = cipher(secret, &salt)?
+            .decrypt(
+                XNonce::from_slice(&nonce),
+                from_hex(&self.ciphertext)?.as_slice(),
+            )
+            .map_err(|_| {
+                EhError::invalid_input(
+                    "Failed to decrypt credentials, wrong key or corrupted file.",
+                )
+            })?;
+        serde_json::from_slice(&plaintext)
+            .map_err(|err| EhError::parse("credentials", err.to_string()))
+    }
+}
+
+/// 从口令与盐派生密钥并创建加密器
+fn cipher(secret: &[u8], salt: &[u8]) -> EhResult<XChaCha20Poly1305> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(secret, salt, &mut key)
+        .map_err(|err| EhError::invalid_input(format!("Failed to derive key: {}", err)))?;
+    Ok(XChaCha20Poly1305::new(Key::from_slice(&key)))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(hex: &str) -> EhResult<Vec<u8>> {
+    if !hex.is_ascii() || !hex.len().is_multiple_of(2) {
+        return Err(EhError::parse("credentials", "Invalid hex string."));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&hex[i..i + 2], 16)
+                .map_err(|err| EhError::parse("credentials", err.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use reqwest::Url;
+
+    use crate::{
+        client::{
+            auth::EhClientAuth,
+            client::EhClient,
+            config::EhClientConfig,
+            cookie::EhCookieStoreConfig,
+            transport::{EhResponse, FixtureTransport},
+        },
+        error::EhError,
+    };
+
+    use super::{EhCredentialKey, EhCredentialsConfig};
+
+    #[test]
+    fn test_credentials_round_trip() {
+        let dir = std::env::temp_dir().join(format!("libeh-credentials-{}", std::process::id()));
+        let path = dir.join("auth.json");
+        let auth = EhClientAuth::new("123456", "secret-hash", Some("secret-igneous"));
+        let key = EhCredentialKey::Passphrase("correct horse".into());
+        let credentials = EhCredentialsConfig::new(&path, key);
+        credentials.save(&auth).unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(!content.contains("secret-hash"));
+        let loaded = credentials.load().unwrap();
+        assert_eq!(loaded.ipb_pass_hash, "secret-hash");
+        assert_eq!(loaded.igneous.as_deref(), Some("secret-igneous"));
+
+        let key_file = dir.join("key");
+        std::fs::write(&key_file, b"correct horse").unwrap();
+        let by_file = EhCredentialsConfig::new(&path, EhCredentialKey::KeyFile(key_file));
+        assert_eq!(by_file.load().unwrap().ipb_member_id, "123456");
+
+        let wrong = EhCredentialsConfig::new(&path, EhCredentialKey::Passphrase("wrong".into()));
+        assert!(matches!(wrong.load(), Err(EhError::InvalidInput(_))));
+        assert!(!format!("{:?}", wrong).contains("wrong"));
+
+        let config = EhClientConfig {
+            credentials: Some(credentials),
+            ..Default::default()
+        };
+        let config = config.load_credentials().unwrap();
+        assert_eq!(config.auth.unwrap().ipb_member_id, "123456");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_credentials_cookie_store() {
+        let dir =
+            std::env::temp_dir().join(format!("libeh-credentials-cookie-{}", std::process::id()));
+        let key = EhCredentialKey::Passphrase("correct horse".into());
+        let credentials = EhCredentialsConfig::new(dir.join("auth.json"), key);
+        let auth = EhClientAuth::new("123456", "secret-hash", Some("secret-igneous"));
+        credentials.save(&auth).unwrap();
+        let url = Url::parse("https://e-hentai.org/").unwrap();
+        let transport = FixtureTransport::new().get(
+            url.as_str(),
+            EhResponse::new(200, "index").header("Set-Cookie", "sk=abcdef; Path=/"),
+        );
+        let config = EhClientConfig {
+            credentials: Some(credentials.clone()),
+            cookie_store: Some(EhCookieStoreConfig::new(dir.join("cookies"))),
+            ..Default::default()
+        };
+        let client = EhClient::new_with_transport(config, Arc::new(transport));
+        client.get_html(url.clone()).await.unwrap();
+        let cookies = client.cookies(&url);
+        assert!(cookies.contains(&("ipb_pass_hash".into(), "secret-hash".into())));
+        let saved = std::fs::read_to_string(dir.join("cookies/123456.json")).unwrap();
+        assert!(saved.contains("abcdef"));
+        assert!(!saved.contains("secret"));
+
+        // 凭据文件无法解密时以未登录的状态创建客户端
+        let wrong = EhCredentialsConfig {
+            key: EhCredentialKey::Passphrase("wrong".into()),
+            ..credentials
+        };
+        let config = EhClientConfig {
+            credentials: Some(wrong),
+            ..Default::default()
+        };
+        let client = EhClient::new_with_transport(config, Arc::new(FixtureTransport::new()));
+        assert!(client.cookies(&url).is_empty());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,174 @@
+use std::time::Duration;
+
+use reqwest::Url;
+
+use crate::{error::SiteError, utils::regex::regex};
+
+const BANNED_STRINGS: [&str; 2] = [
+    "Your IP address has been temporarily banned",
+    "This IP address has been temporarily banned",
+];
+const PATTERN_BAN_EXPIRES: &str = r"The ban expires in (?<expires>[^.<]+)";
+const PATTERN_BAN_DURATION: &str = r"(?<value>\d+) (?<unit>day|hour|minute|second)s?";
+const QUOTA_STRING: &str = "You have exceeded your image viewing limits";
+const QUOTA_PATH_SUFFIX: &str = "/509.gif";
+const PATTERN_IMAGE_SRC: &str = r#"<img[^>]+id="img"[^>]+src="(?<src>[^"]+)""#;
+/// 封禁等纯文本提示页面的最大长度，更长或包含标签的响应不按提示文本判断
+const PLAIN_BODY_MAX_LEN: usize = 1024;
+
+/// 检查响应是否为 IP 封禁、配额耗尽或 Sad Panda 页面
+///
+/// `url` 为响应的最终地址，`content_type` 为响应的 `Content-Type` 头，`body` 为响应文本。
+pub fn detect_site_error(url: &Url, content_type: Option<&str>, body: &str) -> Option<SiteError> {
+    let is_ex = url
+        .host_str()
+        .map(|host| host.ends_with("exhentai.org"))
+        .unwrap_or(false);
+    detect_site_error_with(is_ex, url, content_type, body)
+}
+
+/// 与 [`detect_site_error`] 相同，由调用方指定响应是否来自 ExHentai，用于自定义站点地址
+pub fn detect_site_error_with(
+    is_ex: bool,
+    url: &Url,
+    content_type: Option<&str>,
+    body: &str,
+) -> Option<SiteError> {
+    // 封禁与配额提示为纯文本页面，画廊与搜索页面中的评论、标题可能引用相同的文本
+    let is_plain = body.len() <= PLAIN_BODY_MAX_LEN && !body.contains('<');
+    if is_plain && BANNED_STRINGS.iter().any(|s| body.contains(s)) {
+        return Some(SiteError::IpBanned {
+            expires_in: parse_ban_expires(body),
+        });
+    }
+    if url.path().ends_with(QUOTA_PATH_SUFFIX)
+        || (is_plain && body.contains(QUOTA_STRING))
+        || is_quota_image(body)
+    {
+        return Some(SiteError::QuotaExceeded);
+    }
+    if is_ex {
+        let is_image = content_type
+            .map(|content_type| content_type.starts_with("image/"))
+            .unwrap_or(false);
+        if is_image || body.trim().is_empty() {
+            return Some(SiteError::SadPanda);
+        }
+    }
+    None
+}
+
+/// 图片页面中的图片是否为配额耗尽的提示图片
+fn is_quota_image(body: &str) -> bool {
+    let Ok(r) = regex(PATTERN_IMAGE_SRC) else {
+        return false;
+    };
+    r.captures(body)
+        .and_then(|caps| Url::parse(&caps["src"]).ok())
+        .is_some_and(|src| src.path().ends_with(QUOTA_PATH_SUFFIX))
+}
+
+/// 解析封禁页面中的剩余封禁时长，如 "The ban expires in 2 days and 23 hours"
+pub fn parse_ban_expires(body: &str) -> Option<Duration> {
+    let r = regex(PATTERN_BAN_EXPIRES).ok()?;
+    let caps = r.captures(body)?;
+    let expires = &caps["expires"];
+    let r = regex(PATTERN_BAN_DURATION).ok()?;
+    let mut secs: u64 = 0;
+    let mut matched = false;
+    for caps in r.captures_iter(expires) {
+        let value = caps["value"].parse::<u64>().ok()?;
+        let unit = match &caps["unit"] {
+            "day" => 86400,
+            "hour" => 3600,
+            "minute" => 60,
+            _ => 1,
+        };
+        secs += value * unit;
+        matched = true;
+    }
+    if matched {
+        Some(Duration::from_secs(secs))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use reqwest::Url;
+
+    use crate::error::SiteError;
+
+    use super::{detect_site_error, parse_ban_expires};
+
+    #[test]
+    fn test_detect_ip_ban() {
+        let url = Url::parse("https://e-hentai.org/").unwrap();
+        let body = "Your IP address has been temporarily banned for excessive pageloads which indicates that you are using automated mirroring/harvesting software. The ban expires in 23 hours and 59 minutes";
+        let result = detect_site_error(&url, Some("text/html"), body);
+        assert_eq!(
+            result,
+            Some(SiteError::IpBanned {
+                expires_in: Some(Duration::from_secs(23 * 3600 + 59 * 60))
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_ban_expires() {
+        let body = "This IP address has been temporarily banned due to an excessive request rate. The ban expires in 2 days, 1 hour, 3 minutes and 1 second.";
+        assert_eq!(
+            parse_ban_expires(body),
+            Some(Duration::from_secs(2 * 86400 + 3600 + 3 * 60 + 1))
+        );
+        assert_eq!(parse_ban_expires("The ban expires in a while."), None);
+    }
+
+    #[test]
+    fn test_detect_quota_exceeded() {
+        let url = Url::parse("https://ehgt.org/g/509.gif").unwrap();
+        let result = detect_site_error(&url, Some("image/gif"), "");
+        assert_eq!(result, Some(SiteError::QuotaExceeded));
+        let url = Url::parse("https://e-hentai.org/s/40bc07a79a/618395-11").unwrap();
+        let body = r#"<div id="i3"><img id="img" src="https://ehgt.org/img/509.gif" /></div>"#;
+        let result = detect_site_error(&url, Some("text/html"), body);
+        assert_eq!(result, Some(SiteError::QuotaExceeded));
+    }
+
+    #[test]
+    fn test_detect_quoted_in_page() {
+        let url = Url::parse("https://e-hentai.org/g/618395/0439fa3666/").unwrap();
+        let body = r#"<html><body><div id="gn">Title</div>
+<div class="c6" id="comment_1">Your IP address has been temporarily banned again, see
+<a href="https://ehgt.org/img/509.gif">this</a>. You have exceeded your image viewing limits.</div>
+</body></html>"#;
+        assert_eq!(detect_site_error(&url, Some("text/html"), body), None);
+        let body = "Your IP address has been temporarily banned";
+        assert!(matches!(
+            detect_site_error(&url, Some("text/html"), body),
+            Some(SiteError::IpBanned { .. })
+        ));
+    }
+
+    #[test]
+    fn test_detect_sad_panda() {
+        let url = Url::parse("https://exhentai.org/").unwrap();
+        assert_eq!(
+            detect_site_error(&url, Some("text/html; charset=UTF-8"), ""),
+            Some(SiteError::SadPanda)
+        );
+        assert_eq!(
+            detect_site_error(&url, Some("image/gif"), "GIF89a"),
+            Some(SiteError::SadPanda)
+        );
+        let url = Url::parse("https://e-hentai.org/").unwrap();
+        assert_eq!(detect_site_error(&url, Some("text/html"), ""), None);
+        assert_eq!(
+            detect_site_error(&url, Some("text/html"), "<html></html>"),
+            None
+        );
+    }
+}
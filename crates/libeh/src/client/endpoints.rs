@@ -0,0 +1,193 @@
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    dto::site::{Site, EH_URL, EX_URL},
+    error::{EhError, EhResult},
+};
+
+/// 站点各服务的地址，可指向镜像、反向代理或本地测试服务器
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EhEndpoints {
+    /// E-Hentai 站点地址
+    #[serde(default = "EhEndpoints::default_e_hentai")]
+    pub e_hentai: String,
+    /// ExHentai 站点地址
+    #[serde(default = "EhEndpoints::default_exhentai")]
+    pub exhentai: String,
+    /// API 地址
+    #[serde(default = "EhEndpoints::default_api")]
+    pub api: String,
+    /// 以图搜图地址
+    #[serde(default = "EhEndpoints::default_image_lookup")]
+    pub image_lookup: String,
+    /// 归档下载地址
+    #[serde(default = "EhEndpoints::default_archiver")]
+    pub archiver: String,
+    /// 论坛地址，登录与账号信息使用
+    #[serde(default = "EhEndpoints::default_forums")]
+    pub forums: String,
+}
+
+impl EhEndpoints {
+    fn default_e_hentai() -> String {
+        EH_URL.to_string()
+    }
+
+    fn default_exhentai() -> String {
+        EX_URL.to_string()
+    }
+
+    fn default_api() -> String {
+        "https://api.e-hentai.org/api.php".to_string()
+    }
+
+    fn default_image_lookup() -> String {
+        "https://upld.e-hentai.org/image_lookup.php".to_string()
+    }
+
+    fn default_archiver() -> String {
+        "https://e-hentai.org/archiver.php".to_string()
+    }
+
+    fn default_forums() -> String {
+        "https://forums.e-hentai.org/index.php".to_string()
+    }
+
+    /// 获取站点地址，站点未知时返回错误
+    pub fn site(&self, site: Site) -> EhResult<Url> {
+        match site {
+            Site::Eh => parse("e_hentai", &self.e_hentai),
+            Site::Ex => parse("exhentai", &self.exhentai),
+            Site::Un => Err(EhError::invalid_input("Unrecognized site.")),
+        }
+    }
+
+    /// 获取 API 地址
+    pub fn api(&self) -> EhResult<Url> {
+        parse("api", &self.api)
+    }
+
+    /// 获取以图搜图地址
+    pub fn image_lookup(&self) -> EhResult<Url> {
+        parse("image_lookup", &self.image_lookup)
+    }
+
+    /// 获取归档下载地址
+    pub fn archiver(&self) -> EhResult<Url> {
+        parse("archiver", &self.archiver)
+    }
+
+    /// 获取论坛地址
+    pub fn forums(&self) -> EhResult<Url> {
+        parse("forums", &self.forums)
+    }
+
+    /// 获取论坛登录地址
+    pub fn login(&self) -> EhResult<Url> {
+        let mut url = self.forums()?;
+        url.set_query(Some("act=Login&CODE=01"));
+        Ok(url)
+    }
+
+    /// 检查所有地址是否有效
+    pub fn validate(&self) -> EhResult<()> {
+        self.site(Site::Eh)?;
+        self.site(Site::Ex)?;
+        self.api()?;
+        self.image_lookup()?;
+        self.archiver()?;
+        self.forums()?;
+        Ok(())
+    }
+
+    /// 判断 URL 是否属于 ExHentai
+    pub fn is_exhentai(&self, url: &Url) -> bool {
+        host_of(&self.exhentai).is_some_and(|host| Some(host.as_str()) == url.host_str())
+    }
+
+    /// E-Hentai 与 ExHentai 站点的主机名
+    pub fn site_hosts(&self) -> Vec<String> {
+        [&self.e_hentai, &self.exhentai]
+            .into_iter()
+            .filter_map(|url| host_of(url))
+            .collect()
+    }
+
+    /// API 的主机名
+    pub fn api_host(&self) -> Option<String> {
+        host_of(&self.api)
+    }
+
+    /// 论坛的主机名
+    pub fn forums_host(&self) -> Option<String> {
+        host_of(&self.forums)
+    }
+}
+
+impl Default for EhEndpoints {
+    /// 创建一个使用官方地址的 EhEndpoints 实例
+    fn default() -> Self {
+        EhEndpoints {
+            e_hentai: Self::default_e_hentai(),
+            exhentai: Self::default_exhentai(),
+            api: Self::default_api(),
+            image_lookup: Self::default_image_lookup(),
+            archiver: Self::default_archiver(),
+            forums: Self::default_forums(),
+        }
+    }
+}
+
+/// 解析地址，失败时返回带有字段名的错误
+fn parse(field: &str, url: &str) -> EhResult<Url> {
+    Url::parse(url).map_err(|err| {
+        EhError::invalid_input(format!("Invalid {} endpoint {}: {}", field, url, err))
+    })
+}
+
+/// 获取地址的主机名
+fn host_of(url: &str) -> Option<String> {
+    Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(|host| host.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::Url;
+
+    use crate::dto::site::Site;
+
+    use super::EhEndpoints;
+
+    #[test]
+    fn test_default_endpoints() {
+        let endpoints = EhEndpoints::default();
+        assert!(endpoints.validate().is_ok());
+        assert_eq!(
+            endpoints.site(Site::Ex).unwrap().as_str(),
+            "https://exhentai.org/"
+        );
+        assert_eq!(
+            endpoints.login().unwrap().as_str(),
+            "https://forums.e-hentai.org/index.php?act=Login&CODE=01"
+        );
+        assert!(endpoints.site(Site::Un).is_err());
+        assert!(endpoints.is_exhentai(&Url::parse("https://exhentai.org/g/1/a/").unwrap()));
+        assert_eq!(endpoints.api_host().as_deref(), Some("api.e-hentai.org"));
+    }
+
+    #[test]
+    fn test_endpoints_from_json() {
+        let endpoints: EhEndpoints =
+            serde_json::from_str(r#"{"e_hentai": "http://127.0.0.1:8080/"}"#).unwrap();
+        assert_eq!(endpoints.e_hentai, "http://127.0.0.1:8080/");
+        assert_eq!(endpoints.api, "https://api.e-hentai.org/api.php");
+        let endpoints = EhEndpoints {
+            api: "not a url".into(),
+            ..Default::default()
+        };
+        assert!(endpoints.validate().is_err());
+    }
+}
@@ -0,0 +1,189 @@
+use scraper::Html;
+
+use crate::{
+    dto::gallery::{detail::GalleryDetail, preview::GalleryPreview, show::ShowPage},
+    error::{EhError, EhResult},
+    url::{gallery::GalleryBuilder, show::ShowPageBuilder},
+};
+
+use super::{cancel::EhOutcome, client::EhClient};
+
+/// 获取画廊时的选项
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EhGalleryOptions {
+    /// 是否请求所有预览分页（`?p=N`），默认只包含第一页的预览
+    pub all_previews: bool,
+    /// 是否加载全部评论，默认只包含页面默认显示的评论
+    pub all_comments: bool,
+}
+
+impl EhGalleryOptions {
+    /// 创建一个新的 EhGalleryOptions 实例
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 设置是否请求所有预览分页
+    pub fn all_previews(mut self, all_previews: bool) -> Self {
+        self.all_previews = all_previews;
+        self
+    }
+
+    /// 设置是否加载全部评论
+    pub fn all_comments(mut self, all_comments: bool) -> Self {
+        self.all_comments = all_comments;
+        self
+    }
+}
+
+impl EhClient {
+    /// 获取并解析画廊详情，使用配置的站点
+    ///
+    /// 按选项依次请求其余的预览分页，结果合并到 `preview.pages`。客户端绑定的取消令牌在请求
+    /// 预览分页时被取消，返回 [`EhOutcome::Cancelled`] 与已获取的预览。
+    pub async fn gallery(
+        &self,
+        gid: i64,
+        token: &str,
+        options: EhGalleryOptions,
+    ) -> EhResult<EhOutcome<GalleryDetail>> {
+        let base = self.endpoints.site(self.site)?;
+        let mut builder = GalleryBuilder::new(gid, token);
+        builder.all_comments(options.all_comments);
+        let html = self.get_html(builder.url(&base)).await?;
+        let mut detail = GalleryDetail::parse(html.clone())?;
+        detail.preview = GalleryPreview::parse(&Html::parse_document(&html))?;
+        if !options.all_previews {
+            return Ok(EhOutcome::Complete(detail));
+        }
+        builder.all_comments(false);
+        for p in 1..detail.preview.total_set {
+            let html = match self.get_html(builder.page(p).url(&base)).await {
+                Ok(html) => html,
+                Err(EhError::Cancelled) => return Ok(EhOutcome::Cancelled(detail)),
+                Err(err) => return Err(err),
+            };
+            let pages = GalleryPreview::parse_preview_pages(&Html::parse_document(&html))?;
+            detail.preview.pages.extend(pages);
+        }
+        Ok(EhOutcome::Complete(detail))
+    }
+
+    /// 获取并解析图片页面，使用配置的站点
+    pub async fn show_page(&self, builder: &ShowPageBuilder) -> EhResult<ShowPage> {
+        let base = self.endpoints.site(self.site)?;
+        ShowPage::parse(self.get_html(builder.url(&base)).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        client::{
+            client::EhClient,
+            config::EhClientConfig,
+            transport::{EhResponse, FixtureTransport},
+        },
+        dto::site::Site,
+        url::show::ShowPageBuilder,
+    };
+
+    use super::EhGalleryOptions;
+
+    /// 生成包含一个预览的画廊页面，共 3 个预览分页
+    fn gallery_page(preview: usize) -> String {
+        format!(
+            r#"<html><head><script>
+var gid = 100;
+var token = "abcdef0123";
+var apiuid = -1;
+var apikey = "0123456789abcdef0123";
+</script></head><body>
+<div id="gn">Title</div>
+<div class="gtb"><p class="gpc">Showing 1 - 1 of 3 images</p>
+<table class="ptt"><tr><td>&lt;</td><td>1</td><td>2</td><td>3</td><td>&gt;</td></tr></table></div>
+<div id="gdt"><div class="gdtm"><div style="margin:1px auto 0; width:100px; height:142px; background:transparent url(https://ehgt.org/t/{preview}.jpg) -{preview}00px 0 no-repeat">
+<a href="https://exhentai.org/s/0123456789/100-{preview}"><img alt="{preview}" /></a></div></div></div>
+</body></html>"#,
+            preview = preview
+        )
+    }
+
+    #[tokio::test]
+    async fn test_gallery_previews() {
+        let base = "https://exhentai.org/g/100/abcdef0123/";
+        let transport = Arc::new(
+            FixtureTransport::new()
+                .get(
+                    &format!("{}?hc=1", base),
+                    EhResponse::new(200, gallery_page(1)),
+                )
+                .get(
+                    &format!("{}?p=1", base),
+                    EhResponse::new(200, gallery_page(2)),
+                )
+                .get(
+                    &format!("{}?p=2", base),
+                    EhResponse::new(200, gallery_page(3)),
+                ),
+        );
+        let config = EhClientConfig {
+            site: Site::Ex,
+            ..Default::default()
+        };
+        let client = EhClient::new_with_transport(config, transport.clone());
+        let options = EhGalleryOptions::new()
+            .all_previews(true)
+            .all_comments(true);
+        let outcome = client.gallery(100, "abcdef0123", options).await.unwrap();
+        assert!(!outcome.is_cancelled());
+        let detail = outcome.into_inner();
+        assert_eq!(detail.info.gid, 100);
+        assert_eq!(detail.preview.total_set, 3);
+        let links: Vec<_> = detail
+            .preview
+            .pages
+            .iter()
+            .map(|p| p.link.as_str())
+            .collect();
+        assert_eq!(
+            links,
+            vec![
+                "https://exhentai.org/s/0123456789/100-1",
+                "https://exhentai.org/s/0123456789/100-2",
+                "https://exhentai.org/s/0123456789/100-3",
+            ]
+        );
+        assert_eq!(transport.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_show_page() {
+        let html = r#"<html><head><script>var showkey="0123456789a";</script></head><body>
+<div id="i2"><div class="sn"><div><span>8</span> / <span>20</span></div></div>
+<div>008.jpg :: 1280 x 1808 :: 345.6 KiB</div></div>
+<div id="i3"><img id="img" src="https://abc.hath.network/h/0123/keystamp=1/008.jpg" /></div>
+<div id="i5"><div class="sb"><a href="https://exhentai.org/g/2519745/76939e430f/"></a></div></div>
+</body></html>"#;
+        let transport = Arc::new(FixtureTransport::new().get(
+            "https://exhentai.org/s/d384d63ec0/2519745-8?nl=12345-67890",
+            EhResponse::new(200, html),
+        ));
+        let config = EhClientConfig {
+            site: Site::Ex,
+            ..Default::default()
+        };
+        let client = EhClient::new_with_transport(config, transport.clone());
+        let mut builder = ShowPageBuilder::new(2519745, "d384d63ec0", 8);
+        builder.nl("12345-67890");
+        let page = client.show_page(&builder).await.unwrap();
+        assert_eq!((page.gid, page.token.as_str()), (2519745, "76939e430f"));
+        assert_eq!((page.page, page.total), (8, 20));
+        assert_eq!(page.filename, "008.jpg");
+        assert_eq!(page.showkey.as_deref(), Some("0123456789a"));
+        assert!(page.original.is_none());
+        assert_eq!(transport.requests().len(), 1);
+    }
+}
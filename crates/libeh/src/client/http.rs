@@ -0,0 +1,181 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue},
+    ClientBuilder,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{EhError, EhResult};
+
+/// 默认的 User-Agent
+const DEFAULT_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/121.0.0.0 Safari/537.36 Edg/121.0.0.0";
+
+/// 使用的 HTTP 版本
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum EhHttpVersion {
+    /// 与服务器协商
+    #[default]
+    #[serde(rename = "auto")]
+    Auto,
+    /// 只使用 HTTP/1.1
+    #[serde(rename = "http1")]
+    Http1,
+    /// 直接使用 HTTP/2，服务器必须支持
+    #[serde(rename = "http2")]
+    Http2,
+}
+
+/// HTTP 连接设置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EhHttpConfig {
+    /// 建立连接的超时时间，单位为毫秒，默认为 None，即不限制
+    #[serde(default)]
+    pub connect_timeout_ms: Option<u64>,
+    /// 两次读取响应数据之间的超时时间，单位为毫秒，默认为 None，即不限制
+    #[serde(default)]
+    pub read_timeout_ms: Option<u64>,
+    /// 单次请求的总超时时间，单位为毫秒，默认为 None，即不限制
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    /// User-Agent 请求头
+    #[serde(default = "EhHttpConfig::default_user_agent")]
+    pub user_agent: String,
+    /// 每个请求都会附带的请求头，重定向到其他站点（协议、主机或端口不同）时不会附带
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// 最多跟随的重定向次数，为 0 时不跟随重定向
+    #[serde(default = "EhHttpConfig::default_max_redirects")]
+    pub max_redirects: u32,
+    /// 使用的 HTTP 版本
+    #[serde(default)]
+    pub version: EhHttpVersion,
+    /// 每个主机保留的空闲连接数，默认为 None，即不限制
+    #[serde(default)]
+    pub pool_max_idle_per_host: Option<usize>,
+    /// 空闲连接的保留时间，单位为秒，默认为 None，即使用 reqwest 的默认值
+    #[serde(default)]
+    pub pool_idle_timeout_secs: Option<u64>,
+}
+
+impl EhHttpConfig {
+    fn default_user_agent() -> String {
+        DEFAULT_USER_AGENT.to_string()
+    }
+
+    fn default_max_redirects() -> u32 {
+        20
+    }
+
+    /// 两次读取响应数据之间的超时时间
+    pub fn read_timeout(&self) -> Option<Duration> {
+        self.read_timeout_ms.map(Duration::from_millis)
+    }
+
+    /// 将请求头转换为 HeaderMap，请求头无效时返回错误
+    pub fn header_map(&self) -> EhResult<HeaderMap> {
+        let mut headers = HeaderMap::new();
+        for (key, value) in &self.headers {
+            let name = HeaderName::from_bytes(key.as_bytes())
+                .map_err(|_| EhError::invalid_input(format!("Invalid header name: {}", key)))?;
+            let value = HeaderValue::from_str(value)
+                .map_err(|_| EhError::invalid_input(format!("Invalid value for header {}", key)))?;
+            headers.insert(name, value);
+        }
+        Ok(headers)
+    }
+
+    /// 检查连接设置是否有效
+    pub fn validate(&self) -> EhResult<()> {
+        HeaderValue::from_str(&self.user_agent)
+            .map_err(|_| EhError::invalid_input("Invalid user agent."))?;
+        self.header_map()?;
+        let zero = [
+            ("connect_timeout_ms", self.connect_timeout_ms),
+            ("read_timeout_ms", self.read_timeout_ms),
+            ("timeout_ms", self.timeout_ms),
+        ]
+        .into_iter()
+        .find(|(_, value)| *value == Some(0));
+        if let Some((field, _)) = zero {
+            return Err(EhError::invalid_input(format!(
+                "{} must be positive.",
+                field
+            )));
+        }
+        Ok(())
+    }
+
+    /// 将连接设置应用到 reqwest 客户端构造器，读取超时、重定向与请求头由客户端自行处理
+    pub(crate) fn apply(&self, builder: ClientBuilder) -> EhResult<ClientBuilder> {
+        self.validate()?;
+        let mut builder = builder.user_agent(&self.user_agent);
+        if let Some(timeout) = self.connect_timeout_ms {
+            builder = builder.connect_timeout(Duration::from_millis(timeout));
+        }
+        if let Some(timeout) = self.timeout_ms {
+            builder = builder.timeout(Duration::from_millis(timeout));
+        }
+        builder = match self.version {
+            EhHttpVersion::Auto => builder,
+            EhHttpVersion::Http1 => builder.http1_only(),
+            EhHttpVersion::Http2 => builder.http2_prior_knowledge(),
+        };
+        if let Some(max) = self.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max);
+        }
+        if let Some(secs) = self.pool_idle_timeout_secs {
+            builder = builder.pool_idle_timeout(Duration::from_secs(secs));
+        }
+        Ok(builder)
+    }
+}
+
+impl Default for EhHttpConfig {
+    fn default() -> Self {
+        EhHttpConfig {
+            connect_timeout_ms: None,
+            read_timeout_ms: None,
+            timeout_ms: None,
+            user_agent: Self::default_user_agent(),
+            headers: BTreeMap::new(),
+            max_redirects: Self::default_max_redirects(),
+            version: EhHttpVersion::default(),
+            pool_max_idle_per_host: None,
+            pool_idle_timeout_secs: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EhHttpConfig, EhHttpVersion};
+
+    #[test]
+    fn test_http_config_from_yaml() {
+        let config: EhHttpConfig = serde_yaml::from_str(
+            r#"
+connect_timeout_ms: 5000
+user_agent: libeh-test
+headers:
+  Accept-Language: en-US
+version: http1
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.connect_timeout_ms, Some(5000));
+        assert_eq!(config.max_redirects, 20);
+        assert_eq!(config.version, EhHttpVersion::Http1);
+        assert_eq!(config.header_map().unwrap()["accept-language"], "en-US");
+        assert!(config.validate().is_ok());
+
+        let mut config = EhHttpConfig::default();
+        config.headers.insert("Bad Header".into(), "x".into());
+        assert!(config.validate().is_err());
+        let config = EhHttpConfig {
+            timeout_ms: Some(0),
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+    }
+}
@@ -0,0 +1,313 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use tokio::time::{sleep, Instant};
+
+use super::endpoints::EhEndpoints;
+
+/// 请求优先级，高优先级的请求会在低优先级的请求之前获得令牌
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+pub enum EhPriority {
+    /// 后台任务，如批量抓取
+    #[serde(rename = "background")]
+    Background = 0,
+    /// 普通请求
+    #[default]
+    #[serde(rename = "normal")]
+    Normal = 1,
+    /// 交互式请求，如用户触发的查询
+    #[serde(rename = "interactive")]
+    Interactive = 2,
+}
+
+/// 单个令牌桶的频率限制
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct EhRateLimit {
+    /// 每个周期内允许的请求数
+    pub requests: u32,
+    /// 周期长度，单位为毫秒
+    pub interval_ms: u64,
+    /// 允许的突发请求数，即令牌桶的容量
+    pub burst: u32,
+}
+
+impl EhRateLimit {
+    /// 创建一个新的 EhRateLimit 实例
+    pub fn new(requests: u32, interval_ms: u64, burst: u32) -> Self {
+        EhRateLimit {
+            requests,
+            interval_ms,
+            burst,
+        }
+    }
+
+    /// 生成一个令牌所需的时间
+    fn token_interval(&self) -> Duration {
+        Duration::from_millis(self.interval_ms) / self.requests.max(1)
+    }
+}
+
+/// 请求频率限制设置，按主机类型分为不同的令牌桶，配置中未设置的字段使用默认值
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EhRateLimitConfig {
+    /// e-hentai.org 与 exhentai.org 页面请求共用的限制
+    pub site: EhRateLimit,
+    /// api.e-hentai.org 的请求限制
+    pub api: EhRateLimit,
+    /// 图片服务器的请求限制，每个图片主机各自拥有一个令牌桶
+    pub image: EhRateLimit,
+}
+
+impl EhRateLimitConfig {
+    fn default_site() -> EhRateLimit {
+        EhRateLimit::new(1, 1000, 5)
+    }
+
+    fn default_api() -> EhRateLimit {
+        EhRateLimit::new(4, 5000, 4)
+    }
+
+    fn default_image() -> EhRateLimit {
+        EhRateLimit::new(5, 1000, 10)
+    }
+}
+
+impl Default for EhRateLimitConfig {
+    fn default() -> Self {
+        EhRateLimitConfig {
+            site: Self::default_site(),
+            api: Self::default_api(),
+            image: Self::default_image(),
+        }
+    }
+}
+
+/// 令牌桶的状态
+struct BucketState {
+    /// 当前可用令牌数
+    tokens: f64,
+    /// 上次补充令牌的时间
+    updated: Instant,
+    /// 各优先级正在等待的请求数
+    waiting: [usize; 3],
+}
+
+/// 令牌桶
+struct Bucket {
+    limit: EhRateLimit,
+    state: Mutex<BucketState>,
+}
+
+impl Bucket {
+    fn new(limit: EhRateLimit) -> Self {
+        Bucket {
+            limit,
+            state: Mutex::new(BucketState {
+                tokens: limit.burst.max(1) as f64,
+                updated: Instant::now(),
+                waiting: [0; 3],
+            }),
+        }
+    }
+
+    /// 等待并获取一个令牌
+    async fn acquire(&self, priority: EhPriority) {
+        let level = priority as usize;
+        let _guard = WaitingGuard::new(self, level);
+        loop {
+            let wait = {
+                let mut state = self.state.lock().unwrap();
+                let now = Instant::now();
+                let interval = self.limit.token_interval();
+                if !interval.is_zero() {
+                    let elapsed = now.duration_since(state.updated);
+                    let refill = elapsed.as_secs_f64() / interval.as_secs_f64();
+                    state.tokens = (state.tokens + refill).min(self.limit.burst.max(1) as f64);
+                } else {
+                    state.tokens = self.limit.burst.max(1) as f64;
+                }
+                state.updated = now;
+                let blocked = state.waiting[level + 1..].iter().any(|count| *count > 0);
+                if !blocked && state.tokens >= 1.0 {
+                    state.tokens -= 1.0;
+                    return;
+                }
+                let missing = (1.0 - state.tokens).max(0.0);
+                interval
+                    .mul_f64(missing)
+                    .max(interval.min(Duration::from_millis(10)))
+            };
+            sleep(wait).await;
+        }
+    }
+}
+
+/// 在等待期间登记优先级，并在获取令牌或取消等待时注销
+struct WaitingGuard<'a> {
+    bucket: &'a Bucket,
+    level: usize,
+}
+
+impl<'a> WaitingGuard<'a> {
+    fn new(bucket: &'a Bucket, level: usize) -> Self {
+        bucket.state.lock().unwrap().waiting[level] += 1;
+        WaitingGuard { bucket, level }
+    }
+}
+
+impl Drop for WaitingGuard<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.bucket.state.lock() {
+            state.waiting[self.level] -= 1;
+        }
+    }
+}
+
+/// 请求频率限制器，可在多个任务间共享
+pub struct EhRateLimiter {
+    config: EhRateLimitConfig,
+    buckets: Mutex<HashMap<String, Arc<Bucket>>>,
+    site_hosts: Vec<String>,
+    api_host: Option<String>,
+}
+
+impl EhRateLimiter {
+    /// 创建一个新的 EhRateLimiter 实例，按官方地址区分站点与 API 主机
+    pub fn new(config: EhRateLimitConfig) -> Self {
+        EhRateLimiter {
+            config,
+            buckets: Mutex::new(HashMap::new()),
+            site_hosts: vec![],
+            api_host: None,
+        }
+        .with_endpoints(&EhEndpoints::default())
+    }
+
+    /// 将自定义的站点与 API 主机归入对应的令牌桶
+    pub fn with_endpoints(mut self, endpoints: &EhEndpoints) -> Self {
+        self.site_hosts = endpoints.site_hosts();
+        self.api_host = endpoints.api_host();
+        self
+    }
+
+    /// 等待直到允许向指定 URL 发送请求
+    pub async fn acquire(&self, url: &Url, priority: EhPriority) {
+        let bucket = self.bucket(url);
+        bucket.acquire(priority).await;
+    }
+
+    /// 获取 URL 对应的令牌桶，站点与 API 各共用一个令牌桶，图片服务器按主机区分
+    ///
+    /// 站点主机的子域名（如论坛）归入站点的令牌桶。
+    fn bucket(&self, url: &Url) -> Arc<Bucket> {
+        let host = url.host_str().unwrap_or_default();
+        let is_site = self.site_hosts.iter().any(|site| {
+            host == site
+                || host
+                    .strip_suffix(site.as_str())
+                    .is_some_and(|prefix| prefix.ends_with('.'))
+        });
+        let (key, limit) = if self.api_host.as_deref() == Some(host) {
+            ("api".to_string(), self.config.api)
+        } else if is_site {
+            ("site".to_string(), self.config.site)
+        } else {
+            (format!("image:{}", host), self.config.image)
+        };
+        let mut buckets = self.buckets.lock().unwrap();
+        buckets
+            .entry(key)
+            .or_insert_with(|| Arc::new(Bucket::new(limit)))
+            .clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use reqwest::Url;
+    use tokio::{sync::Mutex, time::Instant};
+
+    use super::{EhPriority, EhRateLimit, EhRateLimitConfig, EhRateLimiter};
+
+    fn limiter() -> EhRateLimiter {
+        EhRateLimiter::new(EhRateLimitConfig {
+            site: EhRateLimit::new(1, 1000, 2),
+            api: EhRateLimit::new(1, 1000, 1),
+            image: EhRateLimit::new(1, 1000, 1),
+        })
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_limit_burst() {
+        let limiter = limiter();
+        let url = Url::parse("https://e-hentai.org/").unwrap();
+        let start = Instant::now();
+        limiter.acquire(&url, EhPriority::Normal).await;
+        limiter.acquire(&url, EhPriority::Normal).await;
+        assert!(start.elapsed() < Duration::from_millis(100));
+        limiter.acquire(&url, EhPriority::Normal).await;
+        assert!(start.elapsed() >= Duration::from_millis(1000));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_limit_buckets() {
+        let limiter = limiter();
+        let start = Instant::now();
+        let site = Url::parse("https://exhentai.org/g/1/abcdef0123/").unwrap();
+        let api = Url::parse("https://api.e-hentai.org/api.php").unwrap();
+        let image_a = Url::parse("https://a.hath.network/h/1.jpg").unwrap();
+        let image_b = Url::parse("https://b.hath.network/h/1.jpg").unwrap();
+        for url in [&site, &api, &image_a, &image_b] {
+            limiter.acquire(url, EhPriority::Normal).await;
+        }
+        assert!(start.elapsed() < Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_rate_limit_hosts() {
+        let limiter = limiter();
+        let bucket = |url: &str| limiter.bucket(&Url::parse(url).unwrap());
+        let site = bucket("https://e-hentai.org/");
+        assert!(Arc::ptr_eq(&site, &bucket("https://exhentai.org/")));
+        assert!(Arc::ptr_eq(&site, &bucket("https://forums.e-hentai.org/")));
+        assert!(!Arc::ptr_eq(&site, &bucket("https://note-hentai.org/")));
+        let api = bucket("https://api.e-hentai.org/api.php");
+        assert!(!Arc::ptr_eq(&site, &api));
+        assert!(!Arc::ptr_eq(&api, &bucket("https://api.example.org/")));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_limit_priority() {
+        let limiter = Arc::new(limiter());
+        let url = Url::parse("https://api.e-hentai.org/api.php").unwrap();
+        limiter.acquire(&url, EhPriority::Normal).await;
+        let order = Arc::new(Mutex::new(vec![]));
+        let mut handles = vec![];
+        for priority in [EhPriority::Background, EhPriority::Interactive] {
+            let limiter = limiter.clone();
+            let url = url.clone();
+            let order = order.clone();
+            handles.push(tokio::spawn(async move {
+                limiter.acquire(&url, priority).await;
+                order.lock().await.push(priority);
+            }));
+            tokio::task::yield_now().await;
+        }
+        for handle in handles {
+            handle.await.unwrap();
+        }
+        let order = order.lock().await;
+        assert_eq!(
+            *order,
+            vec![EhPriority::Interactive, EhPriority::Background]
+        );
+    }
+}
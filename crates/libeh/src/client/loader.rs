@@ -0,0 +1,619 @@
+use std::{
+    collections::BTreeMap,
+    env, fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{
+    de::{self, IntoDeserializer, MapAccess, Visitor},
+    forward_to_deserialize_any, Deserialize, Deserializer, Serialize,
+};
+use serde_json::{map, Map, Value};
+
+use crate::{
+    dto::site::Site,
+    error::{EhError, EhResult},
+};
+
+use super::{
+    config::EhClientConfig,
+    proxy::{EhClientProxy, EhProxyRule},
+};
+
+/// 嵌套字段的环境变量中，字段名之间的分隔符，如 `EH_HTTP__TIMEOUT_MS`
+const ENV_SEPARATOR: &str = "__";
+
+/// 配置值的来源
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EhConfigSource {
+    /// 默认值
+    Default,
+    /// 配置文件
+    File(PathBuf),
+    /// 环境变量
+    Env(String),
+    /// 程序中的覆盖，如命令行参数
+    Override,
+}
+
+impl fmt::Display for EhConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EhConfigSource::Default => write!(f, "default"),
+            EhConfigSource::File(path) => write!(f, "file {}", path.display()),
+            EhConfigSource::Env(name) => write!(f, "env {}", name),
+            EhConfigSource::Override => write!(f, "override"),
+        }
+    }
+}
+
+/// 配置文件格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EhConfigFormat {
+    Toml,
+    Yaml,
+    Json,
+}
+
+impl EhConfigFormat {
+    /// 按扩展名判断配置文件格式
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "toml" => Some(EhConfigFormat::Toml),
+            "yaml" | "yml" => Some(EhConfigFormat::Yaml),
+            "json" => Some(EhConfigFormat::Json),
+            _ => None,
+        }
+    }
+
+    /// 将配置文本解析为 JSON 值
+    pub fn parse(&self, content: &str) -> EhResult<Value> {
+        let value = match self {
+            EhConfigFormat::Toml => toml::from_str::<Value>(content).map_err(|err| err.to_string()),
+            EhConfigFormat::Yaml => {
+                serde_yaml::from_str::<Value>(content).map_err(|err| err.to_string())
+            }
+            EhConfigFormat::Json => {
+                serde_json::from_str::<Value>(content).map_err(|err| err.to_string())
+            }
+        };
+        value.map_err(|err| EhError::invalid_input(format!("Invalid config: {}", err)))
+    }
+}
+
+/// 加载完成的配置与各字段的来源
+#[derive(Debug, Clone)]
+pub struct EhLoadedConfig {
+    /// 合并后的配置
+    pub config: EhClientConfig,
+    /// 各字段的来源，键为以 `.` 分隔的字段路径，如 `http.timeout_ms`
+    pub sources: BTreeMap<String, EhConfigSource>,
+}
+
+impl EhLoadedConfig {
+    /// 获取字段的来源，字段为对象时返回其中最后设置的字段的来源
+    pub fn source(&self, path: &str) -> Option<&EhConfigSource> {
+        self.sources.get(path).or_else(|| {
+            let prefix = format!("{}.", path);
+            self.sources
+                .iter()
+                .filter(|(key, _)| key.starts_with(&prefix))
+                .map(|(_, source)| source)
+                .max_by_key(|source| source_rank(source))
+        })
+    }
+}
+
+/// 分层的配置加载器
+///
+/// 依次叠加默认值、配置文件、环境变量与程序中的覆盖，后加入的层按字段覆盖先前的层，
+/// 对象逐字段合并，数组与其余值整体替换。
+///
+/// ```no_run
+/// # use libeh::client::loader::EhConfigLoader;
+/// # fn main() -> libeh::error::EhResult<()> {
+/// let loaded = EhConfigLoader::new()
+///     .optional_file("config.toml")?
+///     .env()?
+///     .set("http.timeout_ms", 30000)?
+///     .load()?;
+/// println!("{:?}", loaded.source("http.timeout_ms"));
+/// # Ok(())
+/// # }
+/// ```
+pub struct EhConfigLoader {
+    value: Value,
+    sources: BTreeMap<String, EhConfigSource>,
+    /// 从文本解析为数字或布尔值的字段及其原始文本，目标字段为字符串时使用原始文本
+    scalars: BTreeMap<String, String>,
+}
+
+impl EhConfigLoader {
+    /// 创建一个以默认配置为底层的加载器
+    pub fn new() -> Self {
+        let mut loader = EhConfigLoader {
+            value: Value::Object(Map::new()),
+            sources: BTreeMap::new(),
+            scalars: BTreeMap::new(),
+        };
+        let default = serde_json::to_value(EhClientConfig::default())
+            .expect("Default config is serializable.");
+        loader.merge(default, &EhConfigSource::Default);
+        loader
+    }
+
+    /// 叠加配置文件，格式由扩展名决定
+    pub fn file(mut self, path: impl AsRef<Path>) -> EhResult<Self> {
+        let path = path.as_ref();
+        let format = EhConfigFormat::from_path(path).ok_or_else(|| {
+            EhError::invalid_input(format!("Unknown config format: {}", path.display()))
+        })?;
+        let value = format.parse(&fs::read_to_string(path)?)?;
+        self.merge(value, &EhConfigSource::File(path.to_path_buf()));
+        Ok(self)
+    }
+
+    /// 叠加配置文件，文件不存在时跳过
+    pub fn optional_file(self, path: impl AsRef<Path>) -> EhResult<Self> {
+        if path.as_ref().exists() {
+            self.file(path)
+        } else {
+            Ok(self)
+        }
+    }
+
+    /// 叠加当前进程的环境变量
+    pub fn env(self) -> EhResult<Self> {
+        self.env_vars(env::vars())
+    }
+
+    /// 叠加指定的环境变量
+    ///
+    /// 支持 [`EhClientConfig::env`] 读取的 `EH_SITE`、`EH_PROXY`、`EH_PROXY_RULES` 与 `EH_AUTH_*`，
+    /// 其余字段以 `EH_` 加上以 `__` 分隔的大写字段路径设置，如 `EH_HTTP__TIMEOUT_MS=30000`，
+    /// 值能解析为 JSON 时按 JSON 解析，否则视为字符串。解析为数字或布尔值的值用于字符串字段时保留原始文本，
+    /// 如 `EH_AUTH__IPB_MEMBER_ID=123`。
+    pub fn env_vars(mut self, vars: impl IntoIterator<Item = (String, String)>) -> EhResult<Self> {
+        let vars: BTreeMap<String, String> = vars.into_iter().collect();
+        if let Some(site) = vars.get("EH_SITE") {
+            let site = match site.as_str() {
+                "eh" => Site::Eh,
+                "ex" => Site::Ex,
+                site => Site::from(site.to_string()),
+            };
+            if matches!(site, Site::Un) {
+                return Err(EhError::invalid_input("Unrecognized site in EH_SITE."));
+            }
+            self.set_with("site", to_value(site)?, env_source("EH_SITE"));
+        }
+        for name in ["EH_PROXY", "HTTP_PROXY"] {
+            let Some(url) = vars.get(name) else {
+                continue;
+            };
+            let mut proxy = EhClientProxy::parse(url)?;
+            if let Some(no_proxy) = vars.get("EH_NO_PROXY").or_else(|| vars.get("NO_PROXY")) {
+                proxy = proxy.no_proxy(
+                    no_proxy
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|host| !host.is_empty())
+                        .map(str::to_string)
+                        .collect(),
+                );
+            }
+            self.set_with("proxy", to_value(proxy)?, env_source(name));
+            break;
+        }
+        if let Some(rules) = vars.get("EH_PROXY_RULES") {
+            let rules = EhProxyRule::parse_list(rules)?;
+            self.set_with(
+                "proxy_rules",
+                to_value(rules)?,
+                env_source("EH_PROXY_RULES"),
+            );
+        }
+        let auth = [
+            ("EH_AUTH_ID", "auth.ipb_member_id"),
+            ("EH_AUTH_HASH", "auth.ipb_pass_hash"),
+            ("EH_AUTH_IGNEOUS", "auth.igneous"),
+        ];
+        for (name, path) in auth {
+            if let Some(value) = vars.get(name) {
+                self.set_with(path, Value::String(value.clone()), env_source(name));
+            }
+        }
+        for (name, value) in &vars {
+            let Some(path) = name.strip_prefix("EH_") else {
+                continue;
+            };
+            if !path.contains(ENV_SEPARATOR) {
+                continue;
+            }
+            let path = path
+                .split(ENV_SEPARATOR)
+                .map(str::to_ascii_lowercase)
+                .collect::<Vec<_>>()
+                .join(".");
+            self.set_scalar(&path, value, env_source(name));
+        }
+        Ok(self)
+    }
+
+    /// 覆盖一个字段，`path` 为以 `.` 分隔的字段路径
+    pub fn set(mut self, path: &str, value: impl Serialize) -> EhResult<Self> {
+        let value = to_value(value)?;
+        self.set_with(path, value, EhConfigSource::Override);
+        Ok(self)
+    }
+
+    /// 以 `path=value` 的形式覆盖一个字段，如命令行参数 `--set http.timeout_ms=30000`，
+    /// 值能解析为 JSON 时按 JSON 解析，否则视为字符串，规则同 [`env_vars`](Self::env_vars)
+    pub fn set_str(mut self, assignment: &str) -> EhResult<Self> {
+        let (path, value) = assignment.split_once('=').ok_or_else(|| {
+            EhError::invalid_input(format!("Invalid config override: {}", assignment))
+        })?;
+        self.set_scalar(path.trim(), value.trim(), EhConfigSource::Override);
+        Ok(self)
+    }
+
+    /// 合并所有层并检查配置是否有效
+    pub fn load(self) -> EhResult<EhLoadedConfig> {
+        let value = ScalarValue {
+            value: self.value,
+            path: String::new(),
+            scalars: &self.scalars,
+        };
+        let config = EhClientConfig::deserialize(value)
+            .map_err(|err| EhError::invalid_input(format!("Invalid config: {}", err)))?;
+        config.validate()?;
+        Ok(EhLoadedConfig {
+            config,
+            sources: self.sources,
+        })
+    }
+
+    /// 在指定路径上合并一个值
+    fn set_with(&mut self, path: &str, value: Value, source: EhConfigSource) {
+        let mut wrapped = value;
+        for key in path.split('.').rev() {
+            let mut map = Map::new();
+            map.insert(key.to_string(), wrapped);
+            wrapped = Value::Object(map);
+        }
+        self.merge(wrapped, &source);
+    }
+
+    /// 在指定路径上合并一个从文本解析的值，记录解析为数字或布尔值的原始文本
+    fn set_scalar(&mut self, path: &str, text: &str, source: EhConfigSource) {
+        let value = parse_scalar(text);
+        let typed = value.is_number() || value.is_boolean();
+        self.set_with(path, value, source);
+        if typed {
+            self.scalars.insert(path.to_string(), text.to_string());
+        }
+    }
+
+    /// 将一层配置合并到当前配置
+    fn merge(&mut self, value: Value, source: &EhConfigSource) {
+        let mut touched = BTreeMap::new();
+        record_sources(&value, "", source, &mut touched);
+        self.scalars.retain(|path, _| {
+            !touched
+                .keys()
+                .any(|key| path == key || path.starts_with(&format!("{}.", key)))
+        });
+        merge_value(&mut self.value, value, "", source, &mut self.sources);
+    }
+}
+
+impl Default for EhConfigLoader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 递归合并，记录被设置的叶子字段的来源
+fn merge_value(
+    target: &mut Value,
+    value: Value,
+    path: &str,
+    source: &EhConfigSource,
+    sources: &mut BTreeMap<String, EhConfigSource>,
+) {
+    match (target, value) {
+        (Value::Object(target), Value::Object(map)) if !map.is_empty() => {
+            for (key, value) in map {
+                let path = join_path(path, &key);
+                let entry = target.entry(key).or_insert(Value::Null);
+                merge_value(entry, value, &path, source, sources);
+            }
+        }
+        (target, value) => {
+            let prefix = format!("{}.", path);
+            sources.retain(|key, _| !key.starts_with(&prefix));
+            record_sources(&value, path, source, sources);
+            *target = value;
+        }
+    }
+}
+
+/// 记录值中所有叶子字段的来源
+fn record_sources(
+    value: &Value,
+    path: &str,
+    source: &EhConfigSource,
+    sources: &mut BTreeMap<String, EhConfigSource>,
+) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, value) in map {
+                record_sources(value, &join_path(path, key), source, sources);
+            }
+        }
+        _ => {
+            sources.insert(path.to_string(), source.clone());
+        }
+    }
+}
+
+fn join_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+/// 来源的优先级，越大越晚加入
+fn source_rank(source: &EhConfigSource) -> u8 {
+    match source {
+        EhConfigSource::Default => 0,
+        EhConfigSource::File(_) => 1,
+        EhConfigSource::Env(_) => 2,
+        EhConfigSource::Override => 3,
+    }
+}
+
+fn env_source(name: &str) -> EhConfigSource {
+    EhConfigSource::Env(name.to_string())
+}
+
+/// 将字符串解析为 JSON 值，失败时视为字符串
+fn parse_scalar(value: &str) -> Value {
+    serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string()))
+}
+
+/// 反序列化合并后的配置，目标字段为字符串而值为从文本解析的数字或布尔值时使用原始文本
+struct ScalarValue<'a> {
+    value: Value,
+    path: String,
+    scalars: &'a BTreeMap<String, String>,
+}
+
+impl<'de> Deserializer<'de> for ScalarValue<'_> {
+    type Error = serde_json::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.value {
+            Value::Object(map) => visitor.visit_map(ScalarMap {
+                entries: map.into_iter(),
+                path: self.path,
+                scalars: self.scalars,
+                next: None,
+            }),
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.scalars.get(&self.path) {
+            Some(text) if !self.value.is_string() => visitor.visit_string(text.clone()),
+            _ => self.value.deserialize_string(visitor),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.value {
+            Value::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.value.deserialize_enum(name, variants, visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char bytes byte_buf unit
+        unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+/// [`ScalarValue`] 中对象的字段
+struct ScalarMap<'a> {
+    entries: map::IntoIter,
+    path: String,
+    scalars: &'a BTreeMap<String, String>,
+    next: Option<(String, Value)>,
+}
+
+impl<'de> MapAccess<'de> for ScalarMap<'_> {
+    type Error = serde_json::Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: de::DeserializeSeed<'de>,
+    {
+        let Some((key, value)) = self.entries.next() else {
+            return Ok(None);
+        };
+        self.next = Some((key.clone(), value));
+        seed.deserialize(key.into_deserializer()).map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        let (key, value) = self
+            .next
+            .take()
+            .ok_or_else(|| de::Error::custom("value is missing"))?;
+        seed.deserialize(ScalarValue {
+            value,
+            path: join_path(&self.path, &key),
+            scalars: self.scalars,
+        })
+    }
+}
+
+fn to_value(value: impl Serialize) -> EhResult<Value> {
+    serde_json::to_value(value)
+        .map_err(|err| EhError::invalid_input(format!("Invalid config value: {}", err)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::{client::http::EhHttpVersion, error::EhError};
+
+    use super::{EhConfigLoader, EhConfigSource};
+
+    fn vars(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_layered_config() {
+        let path = std::env::temp_dir().join(format!("libeh-config-{}.toml", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"
+site = "ex"
+
+[http]
+timeout_ms = 10000
+version = "http1"
+
+[auth]
+ipb_member_id = "1"
+ipb_pass_hash = "file-hash"
+"#,
+        )
+        .unwrap();
+        let loaded = EhConfigLoader::new()
+            .file(&path)
+            .unwrap()
+            .env_vars(vars(&[
+                ("EH_AUTH_HASH", "env-hash"),
+                ("EH_HTTP__TIMEOUT_MS", "20000"),
+                ("EH_PROXY", "socks5h://127.0.0.1:1080"),
+                ("HOME", "/root"),
+            ]))
+            .unwrap()
+            .set("http.max_redirects", 5)
+            .unwrap()
+            .set_str("http.user_agent=libeh-test")
+            .unwrap()
+            .load()
+            .unwrap();
+        let config = &loaded.config;
+        assert!(matches!(config.site, crate::dto::site::Site::Ex));
+        assert_eq!(config.http.timeout_ms, Some(20000));
+        assert_eq!(config.http.version, EhHttpVersion::Http1);
+        assert_eq!(config.http.max_redirects, 5);
+        assert_eq!(config.http.user_agent, "libeh-test");
+        let auth = config.auth.as_ref().unwrap();
+        assert_eq!(auth.ipb_member_id, "1");
+        assert_eq!(auth.ipb_pass_hash, "env-hash");
+        assert_eq!(config.proxy.as_ref().unwrap().port, 1080);
+
+        let file = EhConfigSource::File(PathBuf::from(&path));
+        assert_eq!(loaded.source("site"), Some(&file));
+        assert_eq!(loaded.source("http.version"), Some(&file));
+        assert_eq!(
+            loaded.source("http.timeout_ms"),
+            Some(&EhConfigSource::Env("EH_HTTP__TIMEOUT_MS".into()))
+        );
+        assert_eq!(loaded.source("auth.ipb_member_id"), Some(&file));
+        assert_eq!(
+            loaded.source("auth.ipb_pass_hash"),
+            Some(&EhConfigSource::Env("EH_AUTH_HASH".into()))
+        );
+        assert_eq!(
+            loaded.source("http.user_agent"),
+            Some(&EhConfigSource::Override)
+        );
+        assert_eq!(
+            loaded.source("endpoints.api"),
+            Some(&EhConfigSource::Default)
+        );
+        assert_eq!(loaded.source("http"), Some(&EhConfigSource::Override));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let result = EhConfigLoader::new()
+            .set("endpoints.api", "not a url")
+            .unwrap()
+            .load();
+        assert!(matches!(result, Err(EhError::InvalidInput(_))));
+        let result = EhConfigLoader::new()
+            .set_str("http.timeout_ms=soon")
+            .unwrap()
+            .load();
+        assert!(matches!(result, Err(EhError::InvalidInput(_))));
+        let result = EhConfigLoader::new().env_vars(vars(&[("EH_SITE", "example.org")]));
+        assert!(result.is_err());
+        assert!(EhConfigLoader::new().file("config.ini").is_err());
+    }
+
+    #[test]
+    fn test_numeric_strings() {
+        let loaded = EhConfigLoader::new()
+            .env_vars(vars(&[
+                ("EH_AUTH__IPB_MEMBER_ID", "123"),
+                ("EH_AUTH__IPB_PASS_HASH", "12345678901234567890123456789012"),
+                ("EH_HTTP__TIMEOUT_MS", "20000"),
+            ]))
+            .unwrap()
+            .set_str("http.user_agent=1.0")
+            .unwrap()
+            .load()
+            .unwrap();
+        let auth = loaded.config.auth.as_ref().unwrap();
+        assert_eq!(auth.ipb_member_id, "123");
+        assert_eq!(auth.ipb_pass_hash, "12345678901234567890123456789012");
+        assert_eq!(loaded.config.http.timeout_ms, Some(20000));
+        assert_eq!(loaded.config.http.user_agent, "1.0");
+
+        let result = EhConfigLoader::new()
+            .set_str("http.user_agent=1.0")
+            .unwrap()
+            .set("http.user_agent", 2)
+            .unwrap()
+            .load();
+        assert!(matches!(result, Err(EhError::InvalidInput(_))));
+    }
+}
@@ -0,0 +1,209 @@
+use reqwest::Url;
+use scraper::Html;
+
+use crate::{
+    dto::site::Site,
+    error::{AuthError, EhError, EhResult, SiteError},
+    utils::scraper::{selector, text_content},
+};
+
+use super::{auth::EhClientAuth, client::EhClient};
+
+const INVALID_CREDENTIALS_STRINGS: [&str; 3] = [
+    "Username or password incorrect",
+    "The username you entered could not be found",
+    "you have entered an incorrect password",
+];
+
+impl EhClient {
+    /// 使用用户名与密码登录 E-Hentai 论坛，返回不包含 igneous 的 EhClientAuth
+    ///
+    /// 登录成功后，获得的 Cookie 会保留在当前客户端中。
+    pub async fn login(&self, username: &str, password: &str) -> EhResult<EhClientAuth> {
+        let url = self.endpoints.login()?;
+        self.login_at(url, username, password).await
+    }
+
+    /// 使用已登录的身份访问 ExHentai，返回包含 igneous 的 EhClientAuth
+    pub async fn fetch_igneous(&self, auth: &EhClientAuth) -> EhResult<EhClientAuth> {
+        let url = self.endpoints.site(Site::Ex)?;
+        self.fetch_igneous_at(url, auth).await
+    }
+
+    async fn login_at(&self, url: Url, username: &str, password: &str) -> EhResult<EhClientAuth> {
+        let mut referer = url.clone();
+        referer.set_query(None);
+        let params = [
+            ("referer", referer.as_str()),
+            ("b", ""),
+            ("bt", ""),
+            ("UserName", username),
+            ("PassWord", password),
+            ("CookieDate", "1"),
+        ];
+        let html = self
+            .fetch_text(self.client.post(url.clone()).form(&params))
+            .await?;
+        let cookies = self.cookies(&url);
+        let member_id = find_cookie(&cookies, "ipb_member_id");
+        let pass_hash = find_cookie(&cookies, "ipb_pass_hash");
+        match (member_id, pass_hash) {
+            (Some(member_id), Some(pass_hash)) => Ok(EhClientAuth::new(member_id, pass_hash, None)),
+            _ => Err(parse_login_error(&html)?.into()),
+        }
+    }
+
+    async fn fetch_igneous_at(&self, url: Url, auth: &EhClientAuth) -> EhResult<EhClientAuth> {
+        for (key, value) in auth.to_vec() {
+            if key != "igneous" {
+                self.jar.add_cookie_str(&format!("{}={}", key, value), &url);
+            }
+        }
+        let client = self.without_cache();
+        match client.fetch_text(client.client.get(url.clone())).await {
+            Ok(_) | Err(EhError::Site(SiteError::SadPanda)) => {}
+            Err(err) => return Err(err),
+        }
+        let cookies = self.cookies(&url);
+        match find_cookie(&cookies, "igneous") {
+            Some(igneous) if igneous != "mystery" => Ok(EhClientAuth::new(
+                &auth.ipb_member_id,
+                &auth.ipb_pass_hash,
+                Some(igneous),
+            )),
+            _ => Err(AuthError::ExHentaiDenied.into()),
+        }
+    }
+
+    /// 获取当前会话中发往指定 URL 的 Cookie 键值对
+    pub(super) fn cookies(&self, url: &Url) -> Vec<(String, String)> {
+        self.jar.get(url)
+    }
+}
+
+/// 在 Cookie 键值对中查找指定的 Cookie
+fn find_cookie<'a>(cookies: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    cookies
+        .iter()
+        .find(|(key, value)| key == name && !value.is_empty())
+        .map(|(_, value)| value.as_str())
+}
+
+/// 解析登录失败页面的原因
+fn parse_login_error(html: &str) -> EhResult<AuthError> {
+    if INVALID_CREDENTIALS_STRINGS.iter().any(|s| html.contains(s)) {
+        return Ok(AuthError::InvalidCredentials);
+    }
+    if html.to_lowercase().contains("captcha") {
+        return Ok(AuthError::CaptchaRequired);
+    }
+    let d = Html::parse_document(html);
+    let s = selector(".errorwrap p, .postcolor")?;
+    let message = d
+        .select(&s)
+        .next()
+        .map(|element| text_content(element.text()))
+        .filter(|message| !message.is_empty())
+        .unwrap_or_else(|| "No login cookies returned.".to_string());
+    Ok(AuthError::Rejected(message))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        client::{
+            auth::EhClientAuth,
+            client::EhClient,
+            config::EhClientConfig,
+            test::{TestResponse, TestServer},
+        },
+        error::{AuthError, EhError},
+    };
+
+    use super::parse_login_error;
+
+    #[test]
+    fn test_parse_login_error() {
+        let html = r#"<div class="errorwrap"><h4>The error returned was:</h4><p>Username or password incorrect</p></div>"#;
+        assert_eq!(
+            parse_login_error(html).unwrap(),
+            AuthError::InvalidCredentials
+        );
+        let html = r#"<form><div class="g-recaptcha" data-sitekey="abc"></div></form>"#;
+        assert_eq!(parse_login_error(html).unwrap(), AuthError::CaptchaRequired);
+        let html = r#"<div class="errorwrap"><p>Your account has been suspended.</p></div>"#;
+        assert_eq!(
+            parse_login_error(html).unwrap(),
+            AuthError::Rejected("Your account has been suspended.".into())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_login() {
+        let server = TestServer::start(vec![
+            (
+                "/index.php",
+                vec![
+                    TestResponse::new(200, "<html>Thanks, you are now logged in</html>")
+                        .header("Set-Cookie", "ipb_member_id=123456; path=/")
+                        .header("Set-Cookie", "ipb_pass_hash=0123456789abcdef; path=/"),
+                ],
+            ),
+            (
+                "/",
+                vec![TestResponse::new(200, "").header("Set-Cookie", "igneous=fedcba9876; path=/")],
+            ),
+        ])
+        .await;
+        let client = EhClient::new(EhClientConfig::default());
+        let auth = client
+            .login_at(server.url("/index.php?act=Login&CODE=01"), "user", "pass")
+            .await
+            .unwrap();
+        assert_eq!(auth.ipb_member_id, "123456");
+        assert_eq!(auth.ipb_pass_hash, "0123456789abcdef");
+        assert_eq!(auth.igneous, None);
+        let request = &server.requests()[0];
+        assert_eq!(request.method, "POST");
+        assert!(request.body.contains("UserName=user"));
+        assert!(request.body.contains("PassWord=pass"));
+
+        let auth = client
+            .fetch_igneous_at(server.url("/"), &auth)
+            .await
+            .unwrap();
+        assert_eq!(auth.igneous.as_deref(), Some("fedcba9876"));
+    }
+
+    #[tokio::test]
+    async fn test_login_failed() {
+        let server = TestServer::start(vec![
+            (
+                "/index.php",
+                vec![TestResponse::new(
+                    200,
+                    r#"<div class="errorwrap"><p>Username or password incorrect</p></div>"#,
+                )],
+            ),
+            (
+                "/",
+                vec![TestResponse::new(200, "").header("Set-Cookie", "igneous=mystery; path=/")],
+            ),
+        ])
+        .await;
+        let client = EhClient::new(EhClientConfig::default());
+        let result = client
+            .login_at(server.url("/index.php?act=Login&CODE=01"), "user", "wrong")
+            .await;
+        assert!(matches!(
+            result,
+            Err(EhError::Auth(AuthError::InvalidCredentials))
+        ));
+        let auth = EhClientAuth::new("123456", "0123456789abcdef", None);
+        let result = client.fetch_igneous_at(server.url("/"), &auth).await;
+        assert!(matches!(
+            result,
+            Err(EhError::Auth(AuthError::ExHentaiDenied))
+        ));
+    }
+}
@@ -0,0 +1,298 @@
+use std::{collections::BTreeMap, fmt::Write, sync::Mutex, time::Duration};
+
+use reqwest::Url;
+
+use crate::error::{EhError, SiteError};
+
+use super::endpoints::EhEndpoints;
+
+/// 请求耗时直方图的分桶上界，单位为秒
+const DURATION_BUCKETS: [f64; 9] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+/// 按 URL 判断请求的类型，用作指标与日志的标签
+pub fn request_kind(endpoints: &EhEndpoints, url: &Url) -> &'static str {
+    let host = url.host_str().unwrap_or_default();
+    if endpoints.api_host().as_deref() == Some(host) {
+        return "api";
+    }
+    if endpoints.forums_host().as_deref() == Some(host) {
+        return "forums";
+    }
+    if !endpoints.site_hosts().iter().any(|site| site == host) {
+        return "image";
+    }
+    let path = url.path();
+    if path.starts_with("/g/") {
+        "gallery"
+    } else if path.starts_with("/s/") {
+        "page"
+    } else if path == "/" || path.starts_with("/tag/") || path.starts_with("/uploader/") {
+        "search"
+    } else {
+        "other"
+    }
+}
+
+/// 错误的类型，用作指标的标签
+fn error_kind(err: &EhError) -> &'static str {
+    match err {
+        EhError::Transport(_) => "transport",
+        EhError::HttpStatus { .. } => "http_status",
+        EhError::Parse { .. } => "parse",
+        EhError::InvalidInput(_) => "invalid_input",
+        EhError::Site(SiteError::IpBanned { .. }) => "ip_banned",
+        EhError::Site(_) => "site",
+        EhError::Auth(_) => "auth",
+        EhError::Io(_) => "io",
+        EhError::CacheMiss(_) => "cache_miss",
+        EhError::Cancelled => "cancelled",
+        EhError::ProxyPoolExhausted { .. } => "proxy_pool_exhausted",
+    }
+}
+
+/// 单个类型的请求耗时直方图
+#[derive(Default)]
+struct Histogram {
+    buckets: [u64; DURATION_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, secs: f64) {
+        for (bucket, le) in self.buckets.iter_mut().zip(DURATION_BUCKETS) {
+            if secs <= le {
+                *bucket += 1;
+            }
+        }
+        self.count += 1;
+        self.sum += secs;
+    }
+}
+
+#[derive(Default)]
+struct MetricsState {
+    /// 按类型与状态统计的请求数
+    requests: BTreeMap<(&'static str, String), u64>,
+    /// 按类型与错误统计的失败请求数
+    errors: BTreeMap<(&'static str, &'static str), u64>,
+    /// 按类型统计的重试次数
+    retries: BTreeMap<&'static str, u64>,
+    /// 按类型统计的响应字节数
+    bytes: BTreeMap<&'static str, u64>,
+    /// 按类型统计的请求耗时
+    durations: BTreeMap<&'static str, Histogram>,
+    /// 封禁次数
+    bans: u64,
+}
+
+/// 客户端的请求指标，可导出为 Prometheus 文本格式
+///
+/// 每个请求在完成所有重试后记录一次，缓存命中的请求不计入。
+#[derive(Default)]
+pub struct EhMetrics {
+    state: Mutex<MetricsState>,
+}
+
+impl EhMetrics {
+    /// 创建一个空的指标集合
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记录一次请求的结果，`attempts` 为包含首次请求在内的尝试次数
+    pub fn record(
+        &self,
+        kind: &'static str,
+        result: Result<usize, &EhError>,
+        attempts: u32,
+        elapsed: Duration,
+    ) {
+        let mut state = self.state.lock().unwrap();
+        let status = match result {
+            Ok(bytes) => {
+                *state.bytes.entry(kind).or_default() += bytes as u64;
+                "ok".to_string()
+            }
+            Err(EhError::HttpStatus { status, .. }) => status.to_string(),
+            Err(_) => "error".to_string(),
+        };
+        if let Err(err) = result {
+            *state.errors.entry((kind, error_kind(err))).or_default() += 1;
+            if matches!(err, EhError::Site(SiteError::IpBanned { .. })) {
+                state.bans += 1;
+            }
+        }
+        *state.requests.entry((kind, status)).or_default() += 1;
+        if attempts > 1 {
+            *state.retries.entry(kind).or_default() += u64::from(attempts - 1);
+        }
+        state
+            .durations
+            .entry(kind)
+            .or_default()
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// 导出为 Prometheus 文本格式
+    pub fn to_prometheus(&self) -> String {
+        let state = self.state.lock().unwrap();
+        let mut out = String::new();
+        header(
+            &mut out,
+            "libeh_requests_total",
+            "counter",
+            "Requests by kind and final status.",
+        );
+        for ((kind, status), value) in &state.requests {
+            let _ = writeln!(
+                out,
+                "libeh_requests_total{{kind=\"{}\",status=\"{}\"}} {}",
+                kind, status, value
+            );
+        }
+        header(
+            &mut out,
+            "libeh_errors_total",
+            "counter",
+            "Failed requests by kind and error.",
+        );
+        for ((kind, error), value) in &state.errors {
+            let _ = writeln!(
+                out,
+                "libeh_errors_total{{kind=\"{}\",error=\"{}\"}} {}",
+                kind, error, value
+            );
+        }
+        header(
+            &mut out,
+            "libeh_retries_total",
+            "counter",
+            "Retries by kind.",
+        );
+        for (kind, value) in &state.retries {
+            let _ = writeln!(out, "libeh_retries_total{{kind=\"{}\"}} {}", kind, value);
+        }
+        header(
+            &mut out,
+            "libeh_response_bytes_total",
+            "counter",
+            "Response body bytes by kind.",
+        );
+        for (kind, value) in &state.bytes {
+            let _ = writeln!(
+                out,
+                "libeh_response_bytes_total{{kind=\"{}\"}} {}",
+                kind, value
+            );
+        }
+        header(&mut out, "libeh_bans_total", "counter", "IP bans detected.");
+        let _ = writeln!(out, "libeh_bans_total {}", state.bans);
+        header(
+            &mut out,
+            "libeh_request_duration_seconds",
+            "histogram",
+            "Request latency including retries.",
+        );
+        for (kind, histogram) in &state.durations {
+            for (le, count) in DURATION_BUCKETS.iter().zip(histogram.buckets) {
+                let _ = writeln!(
+                    out,
+                    "libeh_request_duration_seconds_bucket{{kind=\"{}\",le=\"{}\"}} {}",
+                    kind, le, count
+                );
+            }
+            let _ = writeln!(
+                out,
+                "libeh_request_duration_seconds_bucket{{kind=\"{}\",le=\"+Inf\"}} {}",
+                kind, histogram.count
+            );
+            let _ = writeln!(
+                out,
+                "libeh_request_duration_seconds_sum{{kind=\"{}\"}} {}",
+                kind, histogram.sum
+            );
+            let _ = writeln!(
+                out,
+                "libeh_request_duration_seconds_count{{kind=\"{}\"}} {}",
+                kind, histogram.count
+            );
+        }
+        out
+    }
+}
+
+/// 写入指标的 HELP 与 TYPE 行
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use reqwest::Url;
+
+    use crate::client::{
+        client::EhClient,
+        config::EhClientConfig,
+        endpoints::EhEndpoints,
+        transport::{EhResponse, FixtureTransport},
+    };
+
+    use super::request_kind;
+
+    #[test]
+    fn test_request_kind() {
+        let endpoints = EhEndpoints::default();
+        let kind = |url: &str| request_kind(&endpoints, &Url::parse(url).unwrap());
+        assert_eq!(kind("https://api.e-hentai.org/api.php"), "api");
+        assert_eq!(kind("https://e-hentai.org/g/1/abc/"), "gallery");
+        assert_eq!(kind("https://exhentai.org/s/abc/1-1"), "page");
+        assert_eq!(kind("https://e-hentai.org/?f_search=a"), "search");
+        assert_eq!(kind("https://abc.hath.network/h/1.jpg"), "image");
+        assert_eq!(kind("https://forums.e-hentai.org/index.php"), "forums");
+
+        let endpoints = EhEndpoints {
+            forums: "https://bbs.example.org/".into(),
+            ..Default::default()
+        };
+        let kind = |url: &str| request_kind(&endpoints, &Url::parse(url).unwrap());
+        assert_eq!(kind("https://bbs.example.org/index.php"), "forums");
+        assert_eq!(kind("https://forums.e-hentai.org/index.php"), "image");
+    }
+
+    #[tokio::test]
+    async fn test_prometheus_metrics() {
+        let transport = Arc::new(
+            FixtureTransport::new()
+                .get(
+                    "https://e-hentai.org/g/1/abc/",
+                    EhResponse::new(200, "gallery"),
+                )
+                .get(
+                    "https://e-hentai.org/g/2/abc/",
+                    EhResponse::new(
+                        200,
+                        "Your IP address has been temporarily banned for excessive pageloads.",
+                    ),
+                ),
+        );
+        let config = EhClientConfig {
+            metrics: true,
+            ..Default::default()
+        };
+        let client = EhClient::new_with_transport(config, transport);
+        let url = Url::parse("https://e-hentai.org/g/1/abc/").unwrap();
+        client.get_html(url).await.unwrap();
+        let url = Url::parse("https://e-hentai.org/g/2/abc/").unwrap();
+        assert!(client.get_html(url).await.is_err());
+        let text = client.metrics().unwrap().to_prometheus();
+        assert!(text.contains("libeh_requests_total{kind=\"gallery\",status=\"ok\"} 1"));
+        assert!(text.contains("libeh_errors_total{kind=\"gallery\",error=\"ip_banned\"} 1"));
+        assert!(text.contains("libeh_response_bytes_total{kind=\"gallery\"} 7"));
+        assert!(text.contains("libeh_bans_total 1"));
+        assert!(text.contains("libeh_request_duration_seconds_count{kind=\"gallery\"} 2"));
+    }
+}
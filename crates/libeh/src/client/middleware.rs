@@ -0,0 +1,173 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use reqwest::{Method, Request, Url};
+
+use crate::error::{EhError, EhResult};
+
+use super::{detect::detect_site_error_with, endpoints::EhEndpoints, transport::EhResponse};
+
+/// 中间件看到的请求信息
+#[derive(Debug, Clone, PartialEq)]
+pub struct EhRequestInfo {
+    /// 请求方法
+    pub method: Method,
+    /// 请求的 URL
+    pub url: Url,
+}
+
+impl From<&Request> for EhRequestInfo {
+    fn from(request: &Request) -> Self {
+        EhRequestInfo {
+            method: request.method().clone(),
+            url: request.url().clone(),
+        }
+    }
+}
+
+/// 请求中间件
+///
+/// 每次通过传输层发送请求时调用，重试与重定向的每一跳都会单独调用。中间件按添加顺序调用，
+/// 内置的 [`EhSiteErrorMiddleware`] 总是最先调用。
+#[async_trait]
+pub trait EhMiddleware: Send + Sync {
+    /// 发送请求前调用，可以修改请求头等，返回错误时不发送请求
+    async fn before_request(&self, _request: &mut Request) -> EhResult<()> {
+        Ok(())
+    }
+
+    /// 收到响应后调用，可以检查或修改响应，返回错误时请求以该错误失败
+    async fn after_response(
+        &self,
+        _info: &EhRequestInfo,
+        _response: &mut EhResponse,
+    ) -> EhResult<()> {
+        Ok(())
+    }
+
+    /// 一次请求失败时调用，`info` 为重定向前的原始请求
+    async fn on_error(&self, _info: &EhRequestInfo, _error: &EhError) {}
+}
+
+/// 内置的站点错误检测中间件
+///
+/// 将封禁、配额耗尽与 Sad Panda 页面识别为 [`SiteError`](crate::error::SiteError)，
+/// 其余非成功的状态码转换为 [`EhError::HttpStatus`]，重定向响应交由客户端处理。
+pub struct EhSiteErrorMiddleware {
+    endpoints: Arc<EhEndpoints>,
+}
+
+impl EhSiteErrorMiddleware {
+    /// 创建检测中间件，使用站点地址判断响应是否来自 ExHentai
+    pub fn new(endpoints: Arc<EhEndpoints>) -> Self {
+        EhSiteErrorMiddleware { endpoints }
+    }
+}
+
+#[async_trait]
+impl EhMiddleware for EhSiteErrorMiddleware {
+    async fn after_response(
+        &self,
+        info: &EhRequestInfo,
+        response: &mut EhResponse,
+    ) -> EhResult<()> {
+        let url = &info.url;
+        if (300..400).contains(&response.status) && response.get_header("location").is_some() {
+            return Ok(());
+        }
+        let is_ex = self.endpoints.is_exhentai(url);
+        let content_type = response.get_header("content-type");
+        if let Some(err) = detect_site_error_with(is_ex, url, content_type, &response.body) {
+            return Err(err.into());
+        }
+        if !(200..300).contains(&response.status) {
+            return Err(EhError::HttpStatus {
+                status: response.status,
+                url: url.to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use async_trait::async_trait;
+    use reqwest::{header::HeaderValue, Request};
+
+    use crate::{
+        client::{
+            client::EhClient,
+            config::EhClientConfig,
+            test::{TestResponse, TestServer},
+            transport::EhResponse,
+        },
+        error::{EhError, EhResult},
+    };
+
+    use super::{EhMiddleware, EhRequestInfo};
+
+    /// 添加请求头并记录响应与错误的中间件
+    #[derive(Default)]
+    struct Recorder {
+        events: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl EhMiddleware for Arc<Recorder> {
+        async fn before_request(&self, request: &mut Request) -> EhResult<()> {
+            request
+                .headers_mut()
+                .insert("x-trace", HeaderValue::from_static("1"));
+            Ok(())
+        }
+
+        async fn after_response(
+            &self,
+            info: &EhRequestInfo,
+            response: &mut EhResponse,
+        ) -> EhResult<()> {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("{} {}", response.status, info.url.path()));
+            response.body = response.body.to_uppercase();
+            Ok(())
+        }
+
+        async fn on_error(&self, info: &EhRequestInfo, error: &EhError) {
+            let status = match error {
+                EhError::HttpStatus { status, .. } => *status,
+                _ => 0,
+            };
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("error {} {}", status, info.url.path()));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_middleware_hooks() {
+        let server = TestServer::start(vec![
+            (
+                "/old",
+                vec![TestResponse::new(301, "").header("Location", "/new")],
+            ),
+            ("/new", vec![TestResponse::new(200, "moved")]),
+        ])
+        .await;
+        let recorder = Arc::new(Recorder::default());
+        let client = EhClient::new(EhClientConfig::default()).with_middleware(recorder.clone());
+        assert_eq!(client.get_html(server.url("/old")).await.unwrap(), "MOVED");
+        assert!(client.get_html(server.url("/missing")).await.is_err());
+        assert_eq!(
+            *recorder.events.lock().unwrap(),
+            vec!["301 /old", "200 /new", "error 404 /missing"]
+        );
+        let requests = server.requests();
+        assert!(requests.iter().all(|r| r.header("x-trace") == Some("1")));
+    }
+}
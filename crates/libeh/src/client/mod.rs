@@ -1,4 +1,29 @@
+pub mod accounts;
+pub mod api;
 pub mod auth;
+pub mod cache;
+pub mod cancel;
+pub mod cassette;
+#[allow(clippy::module_inception)]
 pub mod client;
 pub mod config;
+pub mod cookie;
+pub mod credentials;
+pub mod detect;
+pub mod endpoints;
+pub mod gallery;
+pub mod http;
+pub mod limiter;
+pub mod loader;
+pub mod login;
+pub mod metrics;
+pub mod middleware;
+pub mod pool;
 pub mod proxy;
+pub mod resolve;
+pub mod retry;
+pub mod search;
+pub mod session;
+#[cfg(test)]
+pub mod test;
+pub mod transport;
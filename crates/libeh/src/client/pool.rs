@@ -0,0 +1,405 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use reqwest::Request;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{EhError, EhResult, SiteError};
+
+use super::{
+    detect::detect_site_error_with,
+    proxy::{EhClientProxy, EhProxyRule},
+    transport::{EhResponse, EhTransport, ReqwestTransport},
+};
+
+/// 代理池选择代理的方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum EhProxyStrategy {
+    /// 依次轮流使用可用的代理
+    #[default]
+    #[serde(rename = "round_robin")]
+    RoundRobin,
+    /// 优先使用从未被封禁或最早被封禁的代理
+    #[serde(rename = "least_recently_banned")]
+    LeastRecentlyBanned,
+}
+
+/// 代理池设置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EhProxyPoolConfig {
+    /// 代理列表
+    pub proxies: Vec<EhClientProxy>,
+    /// 选择代理的方式，默认为轮流使用
+    #[serde(default)]
+    pub strategy: EhProxyStrategy,
+    /// 代理被移出后的冷却时间，单位为秒，站点给出封禁时长时使用封禁时长，冷却结束后不经检查直接重新使用
+    #[serde(
+        default = "EhProxyPoolConfig::default_cooldown_secs",
+        alias = "readmit_secs"
+    )]
+    pub cooldown_secs: u64,
+    /// 连续连接失败多少次后移出代理，为 0 时不因连接失败移出
+    #[serde(default = "EhProxyPoolConfig::default_max_failures")]
+    pub max_failures: u32,
+}
+
+impl EhProxyPoolConfig {
+    /// 创建一个轮流使用指定代理的代理池设置
+    pub fn new(proxies: Vec<EhClientProxy>) -> Self {
+        EhProxyPoolConfig {
+            proxies,
+            strategy: EhProxyStrategy::default(),
+            cooldown_secs: Self::default_cooldown_secs(),
+            max_failures: Self::default_max_failures(),
+        }
+    }
+
+    /// 设置选择代理的方式
+    pub fn strategy(mut self, strategy: EhProxyStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    fn default_cooldown_secs() -> u64 {
+        900
+    }
+
+    fn default_max_failures() -> u32 {
+        3
+    }
+
+    /// 检查代理池设置是否有效
+    pub fn validate(&self) -> EhResult<()> {
+        if self.proxies.is_empty() {
+            return Err(EhError::invalid_input("Proxy pool is empty."));
+        }
+        self.proxies.iter().try_for_each(EhClientProxy::validate)
+    }
+}
+
+/// 代理池中单个代理的状态
+#[derive(Debug, Clone, PartialEq)]
+pub struct EhProxyStatus {
+    /// 代理
+    pub proxy: EhClientProxy,
+    /// 是否可用
+    pub available: bool,
+    /// 冷却的剩余时间，可用时为 None
+    pub cooldown_remaining: Option<Duration>,
+    /// 被封禁的次数
+    pub bans: u32,
+    /// 连续连接失败的次数
+    pub failures: u32,
+}
+
+/// 代理的运行状态
+#[derive(Default)]
+struct EntryState {
+    /// 移出代理池直到该时间
+    ejected_until: Option<Instant>,
+    /// 上次被封禁的时间
+    last_banned: Option<Instant>,
+    /// 被封禁的次数
+    bans: u32,
+    /// 连续连接失败的次数
+    failures: u32,
+}
+
+impl EntryState {
+    /// 代理当前是否可用，冷却结束时重新使用
+    fn available(&mut self, now: Instant) -> bool {
+        match self.ejected_until {
+            Some(until) if until > now => false,
+            Some(_) => {
+                self.ejected_until = None;
+                self.failures = 0;
+                true
+            }
+            None => true,
+        }
+    }
+}
+
+/// 代理池，记录每个代理的封禁与失败情况
+///
+/// 通过代理的请求触发封禁检测时将代理移出代理池，冷却封禁时长或 `cooldown_secs` 后重新使用。
+/// 代理池不做健康检查，也不会主动探测代理，冷却结束的代理在下次选择代理或获取状态时直接重新使用。
+pub struct EhProxyPool {
+    config: EhProxyPoolConfig,
+    states: Vec<Mutex<EntryState>>,
+    next: AtomicUsize,
+}
+
+impl EhProxyPool {
+    /// 创建一个新的 EhProxyPool 实例
+    pub fn new(config: EhProxyPoolConfig) -> Self {
+        let states = config
+            .proxies
+            .iter()
+            .map(|_| Mutex::new(EntryState::default()))
+            .collect();
+        EhProxyPool {
+            config,
+            states,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// 获取代理池设置
+    pub fn config(&self) -> &EhProxyPoolConfig {
+        &self.config
+    }
+
+    /// 选择一个可用的代理，返回其序号，所有代理都被移出时返回 None
+    pub fn select(&self) -> Option<usize> {
+        let len = self.states.len();
+        let start = self.next.fetch_add(1, Ordering::Relaxed) % len.max(1);
+        let now = Instant::now();
+        let available = (0..len).map(|i| (start + i) % len).filter_map(|index| {
+            let mut state = self.states[index].lock().unwrap();
+            state.available(now).then_some((index, state.last_banned))
+        });
+        match self.config.strategy {
+            EhProxyStrategy::RoundRobin => available.map(|(index, _)| index).next(),
+            // None 小于任何时间，从未被封禁的代理优先
+            EhProxyStrategy::LeastRecentlyBanned => available
+                .min_by_key(|(_, last_banned)| *last_banned)
+                .map(|(index, _)| index),
+        }
+    }
+
+    /// 获取指定序号的代理
+    pub fn proxy(&self, index: usize) -> &EhClientProxy {
+        &self.config.proxies[index]
+    }
+
+    /// 记录代理被封禁并将其移出，`expires_in` 为站点给出的封禁时长
+    pub fn ban(&self, index: usize, expires_in: Option<Duration>) {
+        let now = Instant::now();
+        let cooldown = expires_in.unwrap_or(Duration::from_secs(self.config.cooldown_secs));
+        let mut state = self.states[index].lock().unwrap();
+        state.ejected_until = Some(now + cooldown);
+        state.last_banned = Some(now);
+        state.bans += 1;
+        log::warn!(
+            "Proxy {} banned, cooling down for {} seconds",
+            self.proxy(index),
+            cooldown.as_secs()
+        );
+    }
+
+    /// 记录一次连接失败，连续失败达到 `max_failures` 次时将代理移出
+    pub fn failure(&self, index: usize) {
+        let mut state = self.states[index].lock().unwrap();
+        state.failures += 1;
+        if self.config.max_failures > 0 && state.failures >= self.config.max_failures {
+            state.ejected_until =
+                Some(Instant::now() + Duration::from_secs(self.config.cooldown_secs));
+            log::warn!(
+                "Proxy {} failed {} times, cooling down for {} seconds",
+                self.proxy(index),
+                state.failures,
+                self.config.cooldown_secs
+            );
+        }
+    }
+
+    /// 记录一次成功的请求
+    pub fn success(&self, index: usize) {
+        self.states[index].lock().unwrap().failures = 0;
+    }
+
+    /// 最早结束冷却的代理的剩余冷却时间
+    fn cooldown_remaining(&self) -> Option<Duration> {
+        let now = Instant::now();
+        self.states
+            .iter()
+            .filter_map(|state| state.lock().unwrap().ejected_until)
+            .map(|until| until.saturating_duration_since(now))
+            .min()
+    }
+
+    /// 获取所有代理的状态
+    pub fn status(&self) -> Vec<EhProxyStatus> {
+        let now = Instant::now();
+        self.config
+            .proxies
+            .iter()
+            .zip(&self.states)
+            .map(|(proxy, state)| {
+                let mut state = state.lock().unwrap();
+                let available = state.available(now);
+                EhProxyStatus {
+                    proxy: proxy.clone(),
+                    available,
+                    cooldown_remaining: state
+                        .ejected_until
+                        .map(|until| until.saturating_duration_since(now)),
+                    bans: state.bans,
+                    failures: state.failures,
+                }
+            })
+            .collect()
+    }
+}
+
+/// 通过代理池发送请求的传输层
+///
+/// 请求被封禁或连接失败时换用下一个可用的代理重新发送，所有代理都不可用时返回
+/// [`EhError::ProxyPoolExhausted`]。匹配代理规则的主机不使用代理池。
+pub(super) struct ProxyPoolTransport {
+    pool: Arc<EhProxyPool>,
+    transports: Vec<ReqwestTransport>,
+    rules: Vec<EhProxyRule>,
+    direct: ReqwestTransport,
+}
+
+impl ProxyPoolTransport {
+    /// 创建传输层，`transports` 与代理池中的代理一一对应，`direct` 用于匹配代理规则的主机
+    pub(super) fn new(
+        pool: Arc<EhProxyPool>,
+        transports: Vec<ReqwestTransport>,
+        rules: Vec<EhProxyRule>,
+        direct: ReqwestTransport,
+    ) -> Self {
+        ProxyPoolTransport {
+            pool,
+            transports,
+            rules,
+            direct,
+        }
+    }
+}
+
+#[async_trait]
+impl EhTransport for ProxyPoolTransport {
+    async fn execute(&self, request: Request) -> EhResult<EhResponse> {
+        let host = request.url().host_str().unwrap_or_default();
+        if self.rules.iter().any(|rule| rule.matches(host)) {
+            return self.direct.execute(request).await;
+        }
+        let mut request = request;
+        let mut attempts = 0;
+        loop {
+            let Some(index) = self.pool.select() else {
+                return Err(EhError::ProxyPoolExhausted {
+                    cooldown_remaining: self.pool.cooldown_remaining(),
+                });
+            };
+            attempts += 1;
+            let next = request
+                .try_clone()
+                .filter(|_| attempts < self.transports.len());
+            let url = request.url().clone();
+            match self.transports[index].execute(request).await {
+                Ok(res) => {
+                    let content_type = res.get_header("content-type");
+                    match detect_site_error_with(false, &url, content_type, &res.body) {
+                        Some(SiteError::IpBanned { expires_in }) => {
+                            self.pool.ban(index, expires_in);
+                            match next {
+                                Some(next) => request = next,
+                                None => return Ok(res),
+                            }
+                        }
+                        _ => {
+                            self.pool.success(index);
+                            return Ok(res);
+                        }
+                    }
+                }
+                Err(err) if is_connection_error(&err) => {
+                    self.pool.failure(index);
+                    match next {
+                        Some(next) => request = next,
+                        None => return Err(err),
+                    }
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+/// 是否为连接失败或超时，此类错误计入代理的连续失败次数
+fn is_connection_error(err: &EhError) -> bool {
+    match err {
+        EhError::Transport(err) => err.is_connect() || err.is_timeout(),
+        EhError::Io(err) => err.kind() == std::io::ErrorKind::TimedOut,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::client::proxy::EhClientProxy;
+
+    use super::{EhProxyPool, EhProxyPoolConfig, EhProxyStrategy};
+
+    fn pool(strategy: EhProxyStrategy) -> EhProxyPool {
+        let proxies = (1..=3)
+            .map(|port| EhClientProxy::new("http", "127.0.0.1", port))
+            .collect();
+        EhProxyPool::new(EhProxyPoolConfig::new(proxies).strategy(strategy))
+    }
+
+    #[test]
+    fn test_proxy_pool_select() {
+        let pool = pool(EhProxyStrategy::RoundRobin);
+        let selected: Vec<_> = (0..4).map(|_| pool.select().unwrap()).collect();
+        assert_eq!(selected, vec![0, 1, 2, 0]);
+        pool.ban(1, None);
+        let selected: Vec<_> = (0..3).map(|_| pool.select().unwrap()).collect();
+        assert_eq!(selected, vec![2, 2, 0]);
+        assert!(!pool.status()[1].available);
+        assert_eq!(pool.status()[1].bans, 1);
+
+        pool.ban(0, None);
+        pool.ban(2, None);
+        assert_eq!(pool.select(), None);
+        pool.ban(2, Some(Duration::ZERO));
+        assert_eq!(pool.select(), Some(2));
+
+        let pool = pool_with_failures();
+        pool.failure(0);
+        assert!(pool.status()[0].available);
+        pool.failure(0);
+        assert!(!pool.status()[0].available);
+    }
+
+    fn pool_with_failures() -> EhProxyPool {
+        let mut config = EhProxyPoolConfig::new(vec![EhClientProxy::new("http", "127.0.0.1", 1)]);
+        config.max_failures = 2;
+        EhProxyPool::new(config)
+    }
+
+    #[test]
+    fn test_proxy_pool_least_recently_banned() {
+        let pool = pool(EhProxyStrategy::LeastRecentlyBanned);
+        pool.ban(0, Some(Duration::ZERO));
+        std::thread::sleep(Duration::from_millis(5));
+        pool.ban(1, Some(Duration::ZERO));
+        assert_eq!(pool.select(), Some(2));
+        assert_eq!(pool.select(), Some(2));
+        pool.ban(2, Some(Duration::ZERO));
+        assert_eq!(pool.select(), Some(0));
+    }
+
+    #[test]
+    fn test_proxy_pool_config_cooldown() {
+        let config: EhProxyPoolConfig = serde_json::from_str(r#"{"proxies": []}"#).unwrap();
+        assert_eq!(config.cooldown_secs, 900);
+        // 兼容旧的字段名
+        let config: EhProxyPoolConfig =
+            serde_json::from_str(r#"{"proxies": [], "readmit_secs": 60}"#).unwrap();
+        assert_eq!(config.cooldown_secs, 60);
+    }
+}
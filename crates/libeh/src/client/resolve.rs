@@ -0,0 +1,286 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use hyper::client::connect::dns::Name;
+use reqwest::{
+    dns::{Addrs, Resolve, Resolving},
+    header::ACCEPT,
+    Client, Url,
+};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::error::{EhError, EhResult};
+
+/// DoH 结果缓存，域名与地址及过期时间
+type DohCache = Arc<Mutex<HashMap<String, (Vec<IpAddr>, Instant)>>>;
+
+/// DNS 查询类型 A 与 AAAA
+const DNS_TYPES: [u16; 2] = [1, 28];
+
+/// 域名解析设置
+///
+/// 只替换连接的目标地址，请求的 URL、Host 头与 TLS SNI 保持不变。使用代理时由代理解析域名，
+/// 该设置只作用于不经过代理的请求与 `socks5` 代理。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EhResolveConfig {
+    /// 固定的主机地址，如 `"e-hentai.org": ["104.20.18.168"]`，优先于其余解析方式，主机名不区分大小写
+    #[serde(default, deserialize_with = "deserialize_hosts")]
+    pub hosts: BTreeMap<String, Vec<IpAddr>>,
+    /// DNS-over-HTTPS 服务地址，使用 JSON 格式查询，如 `https://cloudflare-dns.com/dns-query`，
+    /// 默认为 None，即使用系统解析
+    #[serde(default)]
+    pub doh: Option<String>,
+}
+
+impl EhResolveConfig {
+    /// 创建一个新的 EhResolveConfig 实例
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 添加固定的主机地址
+    pub fn host(mut self, host: &str, addrs: Vec<IpAddr>) -> Self {
+        self.hosts.insert(normalize_host(host), addrs);
+        self
+    }
+
+    /// 设置 DNS-over-HTTPS 服务地址
+    pub fn doh(mut self, url: &str) -> Self {
+        self.doh = Some(url.to_string());
+        self
+    }
+
+    /// 检查解析设置是否有效
+    pub fn validate(&self) -> EhResult<()> {
+        if let Some((host, _)) = self.hosts.iter().find(|(_, addrs)| addrs.is_empty()) {
+            return Err(EhError::invalid_input(format!(
+                "No address for host override {}",
+                host
+            )));
+        }
+        if let Some(doh) = &self.doh {
+            Url::parse(doh).map_err(|err| {
+                EhError::invalid_input(format!("Invalid DoH endpoint {}: {}", doh, err))
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// 规范化主机名，去除末尾的点并转为小写
+fn normalize_host(host: &str) -> String {
+    host.trim_end_matches('.').to_ascii_lowercase()
+}
+
+/// 反序列化固定的主机地址，主机名按 [`normalize_host`] 规范化
+fn deserialize_hosts<'de, D>(deserializer: D) -> Result<BTreeMap<String, Vec<IpAddr>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let hosts = BTreeMap::<String, Vec<IpAddr>>::deserialize(deserializer)?;
+    Ok(hosts
+        .into_iter()
+        .map(|(host, addrs)| (normalize_host(&host), addrs))
+        .collect())
+}
+
+/// DoH 的 JSON 响应
+#[derive(Debug, Deserialize)]
+struct DohResponse {
+    #[serde(rename = "Status")]
+    status: u32,
+    #[serde(rename = "Answer", default)]
+    answer: Vec<DohAnswer>,
+}
+
+/// DoH 响应中的一条记录
+#[derive(Debug, Deserialize)]
+struct DohAnswer {
+    #[serde(rename = "type")]
+    kind: u16,
+    #[serde(rename = "TTL", default)]
+    ttl: u64,
+    data: String,
+}
+
+/// 按设置解析域名的解析器，DoH 的结果按记录的 TTL 缓存
+#[derive(Clone)]
+pub struct EhResolver {
+    config: Arc<EhResolveConfig>,
+    client: Client,
+    cache: DohCache,
+}
+
+impl EhResolver {
+    /// 创建解析器，DoH 服务的主机同样使用固定的主机地址
+    pub fn new(config: EhResolveConfig) -> EhResult<Self> {
+        config.validate()?;
+        let mut builder = Client::builder();
+        for (host, addrs) in &config.hosts {
+            let addrs: Vec<_> = addrs.iter().map(|ip| SocketAddr::new(*ip, 0)).collect();
+            builder = builder.resolve_to_addrs(host, &addrs);
+        }
+        Ok(EhResolver {
+            config: Arc::new(config),
+            client: builder.build()?,
+            cache: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+
+    /// 解析域名，依次使用固定的主机地址、DoH 与系统解析
+    pub async fn lookup(&self, host: &str) -> EhResult<Vec<IpAddr>> {
+        let host = normalize_host(host);
+        if let Some(addrs) = self.config.hosts.get(&host) {
+            return Ok(addrs.clone());
+        }
+        let Some(doh) = &self.config.doh else {
+            let addrs = tokio::net::lookup_host((host.as_str(), 0)).await?;
+            return Ok(addrs.map(|addr| addr.ip()).collect());
+        };
+        if let Some((addrs, expires)) = self.cache.lock().unwrap().get(&host) {
+            if *expires > Instant::now() {
+                return Ok(addrs.clone());
+            }
+        }
+        let mut addrs = vec![];
+        let mut ttl = u64::MAX;
+        for kind in DNS_TYPES {
+            let (records, record_ttl) = self.query(doh, &host, kind).await?;
+            addrs.extend(records);
+            ttl = ttl.min(record_ttl);
+        }
+        if addrs.is_empty() {
+            return Err(EhError::invalid_input(format!(
+                "No address found for {}",
+                host
+            )));
+        }
+        let expires = Instant::now() + Duration::from_secs(ttl.min(86400));
+        self.cache
+            .lock()
+            .unwrap()
+            .insert(host, (addrs.clone(), expires));
+        Ok(addrs)
+    }
+
+    /// 通过 DoH 查询一种记录，返回地址与最小的 TTL
+    async fn query(&self, doh: &str, host: &str, kind: u16) -> EhResult<(Vec<IpAddr>, u64)> {
+        let res = self
+            .client
+            .get(doh)
+            .query(&[("name", host), ("type", &kind.to_string())])
+            .header(ACCEPT, "application/dns-json")
+            .send()
+            .await?;
+        if !res.status().is_success() {
+            return Err(EhError::HttpStatus {
+                status: res.status().as_u16(),
+                url: res.url().to_string(),
+            });
+        }
+        let text = res.text().await?;
+        let response: DohResponse =
+            serde_json::from_str(&text).map_err(|err| EhError::parse("doh", err.to_string()))?;
+        // 3 为 NXDOMAIN，视为没有记录
+        if response.status != 0 && response.status != 3 {
+            return Err(EhError::parse(
+                "doh",
+                format!(
+                    "DNS query for {} failed with status {}",
+                    host, response.status
+                ),
+            ));
+        }
+        let records: Vec<_> = response
+            .answer
+            .into_iter()
+            .filter(|answer| answer.kind == kind)
+            .filter_map(|answer| {
+                answer
+                    .data
+                    .parse::<IpAddr>()
+                    .ok()
+                    .map(|ip| (ip, answer.ttl))
+            })
+            .collect();
+        let ttl = records
+            .iter()
+            .map(|(_, ttl)| *ttl)
+            .min()
+            .unwrap_or(u64::MAX);
+        Ok((records.into_iter().map(|(ip, _)| ip).collect(), ttl))
+    }
+}
+
+impl Resolve for EhResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let resolver = self.clone();
+        Box::pin(async move {
+            let addrs = resolver.lookup(name.as_str()).await?;
+            let addrs: Addrs = Box::new(addrs.into_iter().map(|ip| SocketAddr::new(ip, 0)));
+            Ok(addrs)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr};
+
+    use crate::client::test::{TestResponse, TestServer};
+
+    use super::{EhResolveConfig, EhResolver};
+
+    #[tokio::test]
+    async fn test_doh_lookup() {
+        let doh = TestServer::start(vec![
+            (
+                "/dns-query?name=example.invalid&type=1",
+                vec![TestResponse::new(
+                    200,
+                    r#"{"Status":0,"Answer":[{"name":"example.invalid","type":5,"TTL":60,"data":"alias.invalid."},{"name":"alias.invalid","type":1,"TTL":60,"data":"127.0.0.2"}]}"#,
+                )],
+            ),
+            (
+                "/dns-query?name=example.invalid&type=28",
+                vec![TestResponse::new(200, r#"{"Status":0}"#)],
+            ),
+            (
+                "/dns-query?name=missing.invalid",
+                vec![TestResponse::new(200, r#"{"Status":3}"#)],
+            ),
+        ])
+        .await;
+        let localhost = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        let config = EhResolveConfig::new()
+            .host("E-Hentai.org", vec![localhost])
+            .doh(doh.url("/dns-query").as_str());
+        let resolver = EhResolver::new(config).unwrap();
+        assert_eq!(
+            resolver.lookup("e-hentai.org").await.unwrap(),
+            vec![localhost]
+        );
+        let expected = vec!["127.0.0.2".parse::<IpAddr>().unwrap()];
+        assert_eq!(resolver.lookup("example.invalid").await.unwrap(), expected);
+        assert_eq!(resolver.lookup("example.invalid.").await.unwrap(), expected);
+        assert_eq!(doh.requests().len(), 2);
+        assert!(resolver.lookup("missing.invalid").await.is_err());
+        assert!(EhResolveConfig::new().doh("not a url").validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_hosts_case_insensitive() {
+        let config: EhResolveConfig =
+            serde_json::from_str(r#"{"hosts":{"E-Hentai.org.":["127.0.0.1"]}}"#).unwrap();
+        assert!(config.hosts.contains_key("e-hentai.org"));
+        let resolver = EhResolver::new(config).unwrap();
+        assert_eq!(
+            resolver.lookup("E-HENTAI.ORG").await.unwrap(),
+            vec![IpAddr::V4(Ipv4Addr::LOCALHOST)]
+        );
+    }
+}
@@ -0,0 +1,130 @@
+use std::time::Duration;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::error::EhError;
+
+/// 请求失败时的重试策略，配置中未设置的字段使用默认值
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EhRetryPolicy {
+    /// 最大尝试次数，包含首次请求，为 1 时不重试
+    pub max_attempts: u32,
+    /// 首次重试前的等待时间，单位为毫秒，之后每次重试翻倍
+    pub base_delay_ms: u64,
+    /// 单次等待时间的上限，单位为毫秒
+    pub max_delay_ms: u64,
+    /// 是否为等待时间添加随机抖动
+    pub jitter: bool,
+    /// 需要重试的 HTTP 状态码
+    pub retry_statuses: Vec<u16>,
+    /// 是否重试超时的请求
+    pub retry_timeouts: bool,
+    /// 是否重试连接失败或连接被重置的请求
+    pub retry_connect_errors: bool,
+}
+
+impl EhRetryPolicy {
+    /// 不进行任何重试的策略
+    pub fn none() -> Self {
+        EhRetryPolicy {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    /// 判断错误是否可以重试，IP 封禁等站点状况永远不会重试
+    pub fn is_retryable(&self, err: &EhError) -> bool {
+        match err {
+            EhError::Transport(err) => {
+                if err.is_timeout() {
+                    self.retry_timeouts
+                } else if err.is_connect() || err.is_request() || err.is_body() {
+                    self.retry_connect_errors
+                } else {
+                    false
+                }
+            }
+            EhError::HttpStatus { status, .. } => self.retry_statuses.contains(status),
+            EhError::Io(err) if err.kind() == std::io::ErrorKind::TimedOut => self.retry_timeouts,
+            _ => false,
+        }
+    }
+
+    /// 计算第 `attempt` 次重试前的等待时间，`attempt` 从 1 开始
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let exp = attempt.saturating_sub(1).min(31);
+        let delay = self
+            .base_delay_ms
+            .saturating_mul(1u64 << exp)
+            .min(self.max_delay_ms);
+        let delay = if self.jitter && delay > 0 {
+            rand::thread_rng().gen_range(delay / 2..=delay)
+        } else {
+            delay
+        };
+        Duration::from_millis(delay)
+    }
+}
+
+impl Default for EhRetryPolicy {
+    /// 创建一个默认的重试策略：最多尝试 3 次，等待 500 毫秒起，重试超时、连接错误与 5xx 状态码
+    fn default() -> Self {
+        EhRetryPolicy {
+            max_attempts: 3,
+            base_delay_ms: 500,
+            max_delay_ms: 10_000,
+            jitter: true,
+            retry_statuses: vec![500, 502, 503, 504],
+            retry_timeouts: true,
+            retry_connect_errors: true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::error::{EhError, SiteError};
+
+    use super::EhRetryPolicy;
+
+    #[test]
+    fn test_retry_delay() {
+        let policy = EhRetryPolicy {
+            jitter: false,
+            ..Default::default()
+        };
+        assert_eq!(policy.delay(1), Duration::from_millis(500));
+        assert_eq!(policy.delay(2), Duration::from_millis(1000));
+        assert_eq!(policy.delay(3), Duration::from_millis(2000));
+        assert_eq!(policy.delay(10), Duration::from_millis(10_000));
+        let policy = EhRetryPolicy::default();
+        for attempt in 1..5 {
+            let delay = policy.delay(attempt);
+            let max = Duration::from_millis(500 << (attempt - 1));
+            assert!(delay >= max / 2 && delay <= max);
+        }
+    }
+
+    #[test]
+    fn test_retryable_errors() {
+        let policy = EhRetryPolicy::default();
+        let err = EhError::HttpStatus {
+            status: 503,
+            url: "https://e-hentai.org/".into(),
+        };
+        assert!(policy.is_retryable(&err));
+        let err = EhError::HttpStatus {
+            status: 404,
+            url: "https://e-hentai.org/".into(),
+        };
+        assert!(!policy.is_retryable(&err));
+        let err = EhError::Site(SiteError::IpBanned { expires_in: None });
+        assert!(!policy.is_retryable(&err));
+        let err = EhError::Site(SiteError::SadPanda);
+        assert!(!policy.is_retryable(&err));
+    }
+}
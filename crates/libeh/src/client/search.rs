@@ -0,0 +1,352 @@
+use std::collections::VecDeque;
+
+use chrono::{DateTime, Utc};
+use futures::{stream, Stream, StreamExt};
+use reqwest::Url;
+
+use crate::{
+    dto::{gallery::info::GalleryInfo, search_offset::Offset, search_result::SearchResult},
+    error::EhResult,
+    url::search::SearchBuilder,
+};
+
+use super::client::EhClient;
+
+/// 搜索结果流的停止条件，默认不限制
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EhSearchLimits {
+    /// 最多返回的画廊数
+    pub max_items: Option<usize>,
+    /// 最多请求的页数
+    pub max_pages: Option<usize>,
+    /// 遇到或越过该画廊时停止，不包含该画廊
+    pub stop_at_gid: Option<i64>,
+    /// 遇到越过该时间发布的画廊时停止
+    pub stop_at_posted: Option<DateTime<Utc>>,
+}
+
+impl EhSearchLimits {
+    /// 创建一个不限制的 EhSearchLimits 实例
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 设置最多返回的画廊数
+    pub fn max_items(mut self, max_items: usize) -> Self {
+        self.max_items = Some(max_items);
+        self
+    }
+
+    /// 设置最多请求的页数
+    pub fn max_pages(mut self, max_pages: usize) -> Self {
+        self.max_pages = Some(max_pages);
+        self
+    }
+
+    /// 设置遇到该画廊时停止
+    pub fn stop_at_gid(mut self, gid: i64) -> Self {
+        self.stop_at_gid = Some(gid);
+        self
+    }
+
+    /// 设置遇到越过该时间发布的画廊时停止
+    pub fn stop_at_posted(mut self, posted: DateTime<Utc>) -> Self {
+        self.stop_at_posted = Some(posted);
+        self
+    }
+}
+
+/// 搜索结果流的状态
+struct SearchState {
+    client: EhClient,
+    limits: EhSearchLimits,
+    /// 是否向更新的画廊翻页
+    backward: bool,
+    next: Option<Url>,
+    buffer: VecDeque<GalleryInfo>,
+    pages: usize,
+    items: usize,
+}
+
+impl SearchState {
+    /// 画廊是否满足停止条件
+    fn should_stop(&self, info: &GalleryInfo) -> bool {
+        let gid = self
+            .limits
+            .stop_at_gid
+            .is_some_and(|gid| match self.backward {
+                true => info.gid >= gid,
+                false => info.gid <= gid,
+            });
+        let posted = self
+            .limits
+            .stop_at_posted
+            .is_some_and(|posted| match self.backward {
+                true => info.posted > posted,
+                false => info.posted < posted,
+            });
+        gid || posted
+    }
+
+    /// 获取下一个画廊，需要时请求下一页
+    async fn next_item(mut self) -> Option<(EhResult<GalleryInfo>, Self)> {
+        loop {
+            if self.limits.max_items.is_some_and(|max| self.items >= max) {
+                return None;
+            }
+            if let Some(info) = self.buffer.pop_front() {
+                if self.should_stop(&info) {
+                    return None;
+                }
+                self.items += 1;
+                return Some((Ok(info), self));
+            }
+            if self.limits.max_pages.is_some_and(|max| self.pages >= max) {
+                return None;
+            }
+            let url = self.next.take()?;
+            let result = self.client.get_html(url.clone()).await;
+            let result = match result.and_then(SearchResult::parse) {
+                Ok(result) => result,
+                Err(err) => return Some((Err(err), self)),
+            };
+            self.pages += 1;
+            let href = match self.backward {
+                true => result.prev_href,
+                false => result.next_href,
+            };
+            self.next = href.and_then(|href| url.join(&href).ok());
+            let mut list = result.gallery_info_list;
+            if list.is_empty() {
+                self.next = None;
+            }
+            if self.backward {
+                list.reverse();
+            }
+            self.buffer.extend(list);
+        }
+    }
+}
+
+impl EhClient {
+    /// 请求一页搜索结果，未设置站点地址时使用客户端配置的地址
+    pub async fn search_page(&self, builder: SearchBuilder) -> EhResult<SearchResult> {
+        let url = self.search_url(builder)?;
+        SearchResult::parse(self.get_html(url).await?)
+    }
+
+    /// 以流的形式返回搜索结果，自动翻页直到没有更多结果或满足停止条件
+    ///
+    /// 默认向更早发布的画廊翻页。偏移量为 [`Offset::Prev`] 时向更新的画廊翻页，
+    /// 此时按从旧到新的顺序返回。页面中没有结果时结束，请求或解析失败时返回错误后结束，
+    /// 客户端绑定的取消令牌被取消时返回 [`EhError::Cancelled`](crate::error::EhError::Cancelled) 后结束，以便与正常结束区分。
+    pub fn search_stream(
+        &self,
+        builder: SearchBuilder,
+        limits: EhSearchLimits,
+    ) -> impl Stream<Item = EhResult<GalleryInfo>> + Send + 'static {
+        let backward = matches!(builder.current_offset(), Some(Offset::Prev(..)));
+        let (next, error) = match self.search_url(builder) {
+            Ok(url) => (Some(url), None),
+            Err(err) => (None, Some(err)),
+        };
+        let state = SearchState {
+            client: self.clone(),
+            limits,
+            backward,
+            next,
+            buffer: VecDeque::new(),
+            pages: 0,
+            items: 0,
+        };
+        stream::iter(error.map(Err)).chain(stream::unfold(state, SearchState::next_item))
+    }
+
+    /// 生成搜索 URL，未设置站点地址时使用客户端配置的地址
+    fn search_url(&self, builder: SearchBuilder) -> EhResult<Url> {
+        if builder.has_base_url() {
+            return builder.build();
+        }
+        let base = self.endpoints.site(builder.site())?;
+        builder.base_url(base).build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use chrono::{TimeZone, Utc};
+    use futures::StreamExt;
+
+    use crate::{
+        client::{
+            cancel::EhCancelToken,
+            client::EhClient,
+            config::EhClientConfig,
+            transport::{EhResponse, FixtureTransport},
+        },
+        dto::{search_offset::Offset, site::Site},
+        error::EhError,
+        url::search::SearchBuilder,
+    };
+
+    use super::EhSearchLimits;
+
+    /// 生成包含指定画廊的搜索结果页面，画廊 gid 即发布日期的日
+    fn search_page(gids: &[i64], prev: Option<&str>, next: Option<&str>) -> String {
+        let link = |id: &str, href: Option<&str>| match href {
+            Some(href) => format!(r#"<a id="{}" href="{}">link</a>"#, id, href),
+            None => String::new(),
+        };
+        let rows: String = gids
+            .iter()
+            .map(|gid| {
+                format!(
+                    r#"<tr><td><div class="cn">Manga</div></td>
+<td><div class="glthumb"><div><img src="https://ehgt.org/t/{gid}.jpg"></div></div>
+<div><div id="posted_{gid}">2024-01-{gid:02} 12:00</div><div class="ir" style="background-position:0px -1px"></div><div>20 pages</div></div></td>
+<td class="glname"><a href="https://e-hentai.org/g/{gid}/abcdef0123/"><div class="glink">Title {gid}</div></a></td>
+<td class="glhide"><div>uploader</div></td></tr>"#,
+                    gid = gid
+                )
+            })
+            .collect();
+        format!(
+            r#"<html><body><div class="searchnav">{}{}</div><table class="itg">{}</table></body></html>"#,
+            link("uprev", prev),
+            link("unext", next),
+            rows
+        )
+    }
+
+    fn client() -> (EhClient, Arc<FixtureTransport>) {
+        let transport = Arc::new(
+            FixtureTransport::new()
+                .get(
+                    "https://e-hentai.org/?f_search=",
+                    EhResponse::new(200, search_page(&[9, 8, 7], None, Some("/?next=7"))),
+                )
+                .get(
+                    "https://e-hentai.org/?next=7",
+                    EhResponse::new(
+                        200,
+                        search_page(&[6, 5, 4], Some("/?prev=6"), Some("/?next=4")),
+                    ),
+                )
+                .get(
+                    "https://e-hentai.org/?next=4",
+                    EhResponse::new(200, search_page(&[3, 2, 1], Some("/?prev=3"), None)),
+                )
+                .get(
+                    "https://e-hentai.org/?prev=3&f_search=",
+                    EhResponse::new(
+                        200,
+                        search_page(&[6, 5, 4], Some("/?prev=6"), Some("/?next=4")),
+                    ),
+                )
+                .get(
+                    "https://e-hentai.org/?prev=6",
+                    EhResponse::new(200, search_page(&[9, 8, 7], None, Some("/?next=7"))),
+                ),
+        );
+        let client = EhClient::new_with_transport(EhClientConfig::default(), transport.clone());
+        (client, transport)
+    }
+
+    async fn gids(client: &EhClient, builder: SearchBuilder, limits: EhSearchLimits) -> Vec<i64> {
+        client
+            .search_stream(builder, limits)
+            .map(|info| info.unwrap().gid)
+            .collect()
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_search_stream() {
+        let (client, transport) = client();
+        let builder = SearchBuilder::new(Site::Eh);
+        let all = gids(&client, builder.clone(), EhSearchLimits::new()).await;
+        assert_eq!(all, vec![9, 8, 7, 6, 5, 4, 3, 2, 1]);
+        assert_eq!(transport.requests().len(), 3);
+
+        let limits = EhSearchLimits::new().max_items(4);
+        assert_eq!(
+            gids(&client, builder.clone(), limits).await,
+            vec![9, 8, 7, 6]
+        );
+        let limits = EhSearchLimits::new().max_pages(1);
+        assert_eq!(gids(&client, builder.clone(), limits).await, vec![9, 8, 7]);
+        let limits = EhSearchLimits::new().stop_at_gid(5);
+        assert_eq!(
+            gids(&client, builder.clone(), limits).await,
+            vec![9, 8, 7, 6]
+        );
+        let posted = Utc.with_ymd_and_hms(2024, 1, 3, 0, 0, 0).unwrap();
+        let limits = EhSearchLimits::new().stop_at_posted(posted);
+        assert_eq!(
+            gids(&client, builder.clone(), limits).await,
+            vec![9, 8, 7, 6, 5, 4, 3]
+        );
+
+        let backward = builder.offset(Offset::Prev(3, None));
+        let limits = EhSearchLimits::new().stop_at_gid(8);
+        assert_eq!(gids(&client, backward, limits).await, vec![4, 5, 6, 7]);
+
+        let page = client
+            .search_page(SearchBuilder::new(Site::Eh))
+            .await
+            .unwrap();
+        assert_eq!(page.gallery_info_list.len(), 3);
+        assert_eq!(page.next_href.as_deref(), Some("/?next=7"));
+    }
+
+    #[tokio::test]
+    async fn test_search_stream_end() {
+        let no_hits =
+            r#"<html><body><div class="searchnav"></div><p>No hits found</p></body></html>"#;
+        let transport = Arc::new(
+            FixtureTransport::new()
+                .get(
+                    "https://e-hentai.org/?f_search=",
+                    EhResponse::new(200, search_page(&[9, 8, 7], None, Some("/?next=7"))),
+                )
+                .get(
+                    "https://e-hentai.org/?next=7",
+                    EhResponse::new(200, no_hits),
+                ),
+        );
+        let client = EhClient::new_with_transport(EhClientConfig::default(), transport.clone());
+        let builder = SearchBuilder::new(Site::Eh);
+        let all = gids(&client, builder.clone(), EhSearchLimits::new()).await;
+        assert_eq!(all, vec![9, 8, 7]);
+        assert_eq!(transport.requests().len(), 2);
+
+        let token = EhCancelToken::new();
+        token.cancel();
+        let results: Vec<_> = client
+            .with_cancel(token)
+            .search_stream(builder, EhSearchLimits::new())
+            .collect()
+            .await;
+        assert_eq!(results.len(), 1);
+        assert!(matches!(results[0], Err(EhError::Cancelled)));
+    }
+
+    #[tokio::test]
+    async fn test_search_stream_layout_error() {
+        let thumbnail =
+            r#"<html><body><div class="searchnav"></div><div class="itg gld"></div></body></html>"#;
+        let transport = Arc::new(FixtureTransport::new().get(
+            "https://e-hentai.org/?f_search=",
+            EhResponse::new(200, thumbnail),
+        ));
+        let client = EhClient::new_with_transport(EhClientConfig::default(), transport);
+        let results: Vec<_> = client
+            .search_stream(SearchBuilder::new(Site::Eh), EhSearchLimits::new())
+            .collect()
+            .await;
+        assert_eq!(results.len(), 1);
+        assert!(matches!(results[0], Err(EhError::Parse(_))));
+    }
+}
@@ -0,0 +1,217 @@
+use reqwest::Url;
+use scraper::Html;
+
+use crate::{
+    dto::site::Site,
+    error::{AuthError, EhError, EhResult, SiteError},
+    utils::{
+        regex::regex,
+        scraper::{selector, text_content},
+    },
+};
+
+use super::client::EhClient;
+
+const PATTERN_MEMBER_ID: &str = r"showuser=(\d+)";
+const REQUIRED_COOKIES: [&str; 3] = ["ipb_member_id", "ipb_pass_hash", "igneous"];
+
+/// 当前会话的登录状态
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EhSessionStatus {
+    /// 是否已登录 E-Hentai
+    pub logged_in: bool,
+    /// 用户 ID
+    pub member_id: Option<String>,
+    /// 用户名
+    pub member_name: Option<String>,
+    /// 是否可以访问 ExHentai
+    pub exhentai: bool,
+    /// 当前会话缺少的身份验证 Cookie
+    pub missing_cookies: Vec<String>,
+}
+
+impl EhSessionStatus {
+    /// 检查会话能否访问指定站点，不能访问时返回对应的错误
+    pub fn ensure(&self, site: Site) -> EhResult<()> {
+        if !self.logged_in {
+            return Err(AuthError::NotLoggedIn.into());
+        }
+        if matches!(site, Site::Ex) && !self.exhentai {
+            return Err(AuthError::ExHentaiDenied.into());
+        }
+        Ok(())
+    }
+}
+
+impl EhClient {
+    /// 检查当前会话的登录状态与 ExHentai 访问权限
+    ///
+    /// 会请求 E-Hentai 论坛首页，登录成功时再请求 ExHentai 首页。
+    pub async fn check_session(&self) -> EhResult<EhSessionStatus> {
+        let forums = self.endpoints.forums()?;
+        let exhentai = self.endpoints.site(Site::Ex)?;
+        self.check_session_at(forums, exhentai).await
+    }
+
+    async fn check_session_at(&self, forums: Url, exhentai: Url) -> EhResult<EhSessionStatus> {
+        let missing_cookies = self.missing_cookies(&exhentai);
+        let client = self.without_cache();
+        let html = client.get_html(forums).await?;
+        let (member_id, member_name) = parse_forum_member(&html)?;
+        let logged_in = member_id.is_some();
+        let exhentai = logged_in
+            && match client.get_html(exhentai).await {
+                Ok(html) => !html.trim().is_empty(),
+                Err(EhError::Site(SiteError::SadPanda)) => false,
+                Err(err) => return Err(err),
+            };
+        Ok(EhSessionStatus {
+            logged_in,
+            member_id,
+            member_name,
+            exhentai,
+            missing_cookies,
+        })
+    }
+
+    /// 获取发往指定 URL 时缺少的身份验证 Cookie，值为 "mystery" 的 igneous 视为缺少
+    fn missing_cookies(&self, url: &Url) -> Vec<String> {
+        let cookies = self.cookies(url);
+        REQUIRED_COOKIES
+            .iter()
+            .filter(|name| {
+                !cookies
+                    .iter()
+                    .any(|(key, value)| key == *name && !value.is_empty() && value != "mystery")
+            })
+            .map(|name| name.to_string())
+            .collect()
+    }
+}
+
+/// 从论坛页面解析已登录用户的 ID 与用户名，未登录时均为 None
+fn parse_forum_member(html: &str) -> EhResult<(Option<String>, Option<String>)> {
+    let d = Html::parse_document(html);
+    let s = selector("#userlinks a[href*='showuser=']")?;
+    let Some(link) = d.select(&s).next() else {
+        return Ok((None, None));
+    };
+    let re = regex(PATTERN_MEMBER_ID)?;
+    let member_id = link
+        .value()
+        .attr("href")
+        .and_then(|href| re.captures(href))
+        .map(|caps| caps[1].to_string());
+    let member_name = Some(text_content(link.text())).filter(|name| !name.is_empty());
+    Ok((member_id, member_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::Url;
+
+    use crate::{
+        client::{
+            auth::EhClientAuth,
+            client::EhClient,
+            config::EhClientConfig,
+            test::{TestResponse, TestServer},
+        },
+        dto::site::Site,
+        error::{AuthError, EhError},
+    };
+
+    use super::{parse_forum_member, EhSessionStatus};
+
+    const LOGGED_IN: &str = r#"<div id="userlinks"><p class="home"><b>Logged in as:  <a href="https://forums.e-hentai.org/index.php?showuser=123456">tester</a></b></p></div>"#;
+    const GUEST: &str =
+        r#"<div id="userlinksguest"><p class="pcen"><b>Welcome Guest</b></p></div>"#;
+
+    #[test]
+    fn test_parse_forum_member() {
+        assert_eq!(
+            parse_forum_member(LOGGED_IN).unwrap(),
+            (Some("123456".into()), Some("tester".into()))
+        );
+        assert_eq!(parse_forum_member(GUEST).unwrap(), (None, None));
+    }
+
+    #[test]
+    fn test_session_ensure() {
+        let status = EhSessionStatus {
+            logged_in: true,
+            exhentai: false,
+            ..Default::default()
+        };
+        assert!(status.ensure(Site::Eh).is_ok());
+        assert!(matches!(
+            status.ensure(Site::Ex),
+            Err(EhError::Auth(AuthError::ExHentaiDenied))
+        ));
+        assert!(matches!(
+            EhSessionStatus::default().ensure(Site::Eh),
+            Err(EhError::Auth(AuthError::NotLoggedIn))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_check_session() {
+        let server = TestServer::start(vec![
+            ("/index.php", vec![TestResponse::new(200, LOGGED_IN)]),
+            ("/", vec![TestResponse::new(200, "<html>front page</html>")]),
+        ])
+        .await;
+        let config = EhClientConfig {
+            auth: Some(EhClientAuth::new("123456", "0123456789abcdef", None)),
+            ..Default::default()
+        };
+        let client = EhClient::new(config);
+        let status = client
+            .check_session_at(server.url("/index.php"), server.url("/"))
+            .await
+            .unwrap();
+        assert!(status.logged_in);
+        assert_eq!(status.member_id.as_deref(), Some("123456"));
+        assert_eq!(status.member_name.as_deref(), Some("tester"));
+        assert!(status.exhentai);
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[test]
+    fn test_missing_cookies() {
+        let url = Url::parse("https://exhentai.org/").unwrap();
+        let client = EhClient::new(EhClientConfig::default());
+        assert_eq!(client.missing_cookies(&url).len(), 3);
+        let config = EhClientConfig {
+            auth: Some(EhClientAuth::new("123456", "0123456789abcdef", None)),
+            ..Default::default()
+        };
+        let client = EhClient::new(config);
+        assert_eq!(client.missing_cookies(&url), vec!["igneous"]);
+        let config = EhClientConfig {
+            auth: Some(EhClientAuth::new(
+                "123456",
+                "0123456789abcdef",
+                Some("mystery"),
+            )),
+            ..Default::default()
+        };
+        let client = EhClient::new(config);
+        assert_eq!(client.missing_cookies(&url), vec!["igneous"]);
+    }
+
+    #[tokio::test]
+    async fn test_check_session_guest() {
+        let server =
+            TestServer::start(vec![("/index.php", vec![TestResponse::new(200, GUEST)])]).await;
+        let client = EhClient::new(EhClientConfig::default());
+        let status = client
+            .check_session_at(server.url("/index.php"), server.url("/"))
+            .await
+            .unwrap();
+        assert!(!status.logged_in);
+        assert!(!status.exhentai);
+        assert_eq!(status.missing_cookies.len(), 3);
+        assert_eq!(server.requests().len(), 1);
+    }
+}
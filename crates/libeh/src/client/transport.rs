@@ -0,0 +1,291 @@
+use std::{io, sync::Mutex, time::Duration};
+
+use async_trait::async_trait;
+use reqwest::{Client, Method, Request};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{EhError, EhResult};
+
+/// 传输层返回的响应
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EhResponse {
+    /// HTTP 状态码
+    pub status: u16,
+    /// 响应头，同名的响应头可出现多次
+    #[serde(default)]
+    pub headers: Vec<(String, String)>,
+    /// 响应文本
+    #[serde(default)]
+    pub body: String,
+}
+
+impl EhResponse {
+    /// 创建一个新的 EhResponse 实例
+    pub fn new(status: u16, body: impl Into<String>) -> Self {
+        EhResponse {
+            status,
+            headers: vec![],
+            body: body.into(),
+        }
+    }
+
+    /// 添加一个响应头
+    pub fn header(mut self, key: &str, value: &str) -> Self {
+        self.headers.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    /// 获取第一个指定名称的响应头，名称不区分大小写
+    pub fn get_header(&self, key: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// 获取所有指定名称的响应头，名称不区分大小写
+    pub fn get_headers<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.headers
+            .iter()
+            .filter(move |(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// 传输层收到的请求，用于记录与匹配
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EhRecordedRequest {
+    /// 请求方法
+    pub method: String,
+    /// 请求的 URL
+    pub url: String,
+    /// 请求体，没有请求体时为空
+    #[serde(default)]
+    pub body: String,
+}
+
+impl From<&Request> for EhRecordedRequest {
+    fn from(request: &Request) -> Self {
+        let body = request
+            .body()
+            .and_then(|body| body.as_bytes())
+            .map(|body| String::from_utf8_lossy(body).to_string())
+            .unwrap_or_default();
+        EhRecordedRequest {
+            method: request.method().to_string(),
+            url: request.url().to_string(),
+            body,
+        }
+    }
+}
+
+/// HTTP 传输层，负责发送单个请求
+///
+/// 传输层不需要处理重定向与 Cookie，二者由 [`EhClient`](super::client::EhClient) 负责。
+#[async_trait]
+pub trait EhTransport: Send + Sync {
+    /// 发送请求并读取完整的响应
+    async fn execute(&self, request: Request) -> EhResult<EhResponse>;
+}
+
+/// 基于 reqwest 的默认传输层
+pub struct ReqwestTransport {
+    client: Client,
+    read_timeout: Option<Duration>,
+}
+
+impl ReqwestTransport {
+    /// 使用 reqwest 客户端创建传输层，客户端不应自动跟随重定向
+    pub fn new(client: Client) -> Self {
+        ReqwestTransport {
+            client,
+            read_timeout: None,
+        }
+    }
+
+    /// 设置等待响应头与两次读取响应数据之间的超时时间
+    pub fn with_read_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.read_timeout = timeout;
+        self
+    }
+}
+
+/// 在超时时间内等待读取完成，超时返回 [`io::ErrorKind::TimedOut`]
+async fn read_within<T>(
+    timeout: Option<Duration>,
+    read: impl std::future::Future<Output = reqwest::Result<T>>,
+) -> EhResult<T> {
+    let Some(timeout) = timeout else {
+        return Ok(read.await?);
+    };
+    match tokio::time::timeout(timeout, read).await {
+        Ok(result) => Ok(result?),
+        Err(_) => Err(EhError::Io(io::Error::new(
+            io::ErrorKind::TimedOut,
+            "Read timed out.",
+        ))),
+    }
+}
+
+#[async_trait]
+impl EhTransport for ReqwestTransport {
+    async fn execute(&self, request: Request) -> EhResult<EhResponse> {
+        let mut res = read_within(self.read_timeout, self.client.execute(request)).await?;
+        let status = res.status().as_u16();
+        let headers = res
+            .headers()
+            .iter()
+            .filter_map(|(key, value)| {
+                value
+                    .to_str()
+                    .ok()
+                    .map(|value| (key.to_string(), value.to_string()))
+            })
+            .collect();
+        let mut body = vec![];
+        while let Some(chunk) = read_within(self.read_timeout, res.chunk()).await? {
+            body.extend_from_slice(&chunk);
+        }
+        let body = String::from_utf8_lossy(&body).to_string();
+        Ok(EhResponse {
+            status,
+            headers,
+            body,
+        })
+    }
+}
+
+/// 预设响应的内存传输层，用于离线测试
+///
+/// 按请求方法与完整 URL 匹配预设的响应，同一请求的多个响应依次返回，用尽后重复最后一个，
+/// 没有匹配的请求返回 404。
+#[derive(Default)]
+pub struct FixtureTransport {
+    fixtures: Mutex<Vec<(Method, String, Vec<EhResponse>)>>,
+    requests: Mutex<Vec<EhRecordedRequest>>,
+}
+
+impl FixtureTransport {
+    /// 创建一个没有预设响应的传输层
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 为指定的请求方法与 URL 添加一个响应
+    pub fn route(self, method: Method, url: &str, response: EhResponse) -> Self {
+        {
+            let mut fixtures = self.fixtures.lock().unwrap();
+            match fixtures
+                .iter_mut()
+                .find(|(m, u, _)| *m == method && u == url)
+            {
+                Some((_, _, responses)) => responses.push(response),
+                None => fixtures.push((method, url.to_string(), vec![response])),
+            }
+        }
+        self
+    }
+
+    /// 为指定 URL 的 GET 请求添加一个响应
+    pub fn get(self, url: &str, response: EhResponse) -> Self {
+        self.route(Method::GET, url, response)
+    }
+
+    /// 为指定 URL 的 POST 请求添加一个响应
+    pub fn post(self, url: &str, response: EhResponse) -> Self {
+        self.route(Method::POST, url, response)
+    }
+
+    /// 获取传输层收到的所有请求
+    pub fn requests(&self) -> Vec<EhRecordedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl EhTransport for FixtureTransport {
+    async fn execute(&self, request: Request) -> EhResult<EhResponse> {
+        let recorded = EhRecordedRequest::from(&request);
+        self.requests.lock().unwrap().push(recorded);
+        let mut fixtures = self.fixtures.lock().unwrap();
+        let response = fixtures
+            .iter_mut()
+            .find(|(method, url, _)| method == request.method() && url == request.url().as_str())
+            .map(|(_, _, responses)| {
+                if responses.len() > 1 {
+                    responses.remove(0)
+                } else {
+                    responses[0].clone()
+                }
+            })
+            .unwrap_or_else(|| EhResponse::new(404, "Not Found"));
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use reqwest::Url;
+
+    use crate::{
+        client::{client::EhClient, config::EhClientConfig},
+        error::{EhError, EhResult, SiteError},
+    };
+
+    use super::{EhResponse, FixtureTransport};
+
+    #[tokio::test]
+    async fn test_fixture_redirect_and_cookies() {
+        let transport = Arc::new(
+            FixtureTransport::new()
+                .post(
+                    "https://e-hentai.org/uconfig.php",
+                    EhResponse::new(302, "")
+                        .header("Location", "/home.php")
+                        .header("Set-Cookie", "uconfig=dm_l; Domain=e-hentai.org; Path=/"),
+                )
+                .get(
+                    "https://e-hentai.org/home.php",
+                    EhResponse::new(200, "home"),
+                ),
+        );
+        let client = EhClient::new_with_transport(EhClientConfig::default(), transport.clone());
+        let url = Url::parse("https://e-hentai.org/uconfig.php").unwrap();
+        let result: EhResult<String> = client.fetch_text(client.client.post(url).body("a=1")).await;
+        assert_eq!(result.unwrap(), "home");
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1].method, "GET");
+        assert_eq!(requests[1].url, "https://e-hentai.org/home.php");
+        let cookies = client.cookies(&Url::parse("https://api.e-hentai.org/").unwrap());
+        assert_eq!(cookies, vec![("uconfig".to_string(), "dm_l".to_string())]);
+    }
+
+    #[tokio::test]
+    async fn test_fixture_site_error() {
+        let transport = Arc::new(FixtureTransport::new().get(
+            "https://e-hentai.org/",
+            EhResponse::new(
+                200,
+                "Your IP address has been temporarily banned for excessive pageloads.",
+            ),
+        ));
+        let client = EhClient::new_with_transport(EhClientConfig::default(), transport);
+        let result = client
+            .get_html(Url::parse("https://e-hentai.org/").unwrap())
+            .await;
+        assert!(matches!(
+            result,
+            Err(EhError::Site(SiteError::IpBanned { .. }))
+        ));
+        let result = client
+            .get_html(Url::parse("https://e-hentai.org/missing").unwrap())
+            .await;
+        assert!(matches!(
+            result,
+            Err(EhError::HttpStatus { status: 404, .. })
+        ));
+    }
+}
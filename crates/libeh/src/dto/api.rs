@@ -1,15 +1,24 @@
+use std::str::FromStr;
+
 use chrono::{DateTime, Utc};
+use reqwest::Url;
 use serde::{Deserialize, Serialize};
 
-use crate::utils::serde::{
-    parse_float32_str, parse_int32_str, parse_int64_str, parse_keyword_strings,
-    parse_option_int64_str, parse_unix_timestamp_str,
+use crate::{
+    error::{EhError, EhResult},
+    utils::{
+        regex::regex,
+        serde::{
+            parse_float32_str, parse_int32_str, parse_int64_str, parse_keyword_strings,
+            parse_option_int64_str, parse_unix_timestamp_str,
+        },
+    },
 };
 
 use super::keyword::Keyword;
 
 /// 画廊 ID 及其令牌
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct GIDListItem(i64, String);
 
 impl GIDListItem {
@@ -17,26 +26,67 @@ impl GIDListItem {
     pub fn new(gid: i64, token: &str) -> Self {
         GIDListItem(gid, token.into())
     }
+
+    /// 画廊 ID
+    pub fn gid(&self) -> i64 {
+        self.0
+    }
+
+    /// 画廊令牌
+    pub fn token(&self) -> &str {
+        &self.1
+    }
+}
+
+impl FromStr for GIDListItem {
+    type Err = EhError;
+
+    /// 从画廊 URL 解析画廊 ID 及其令牌，不限制站点的域名
+    fn from_str(value: &str) -> EhResult<Self> {
+        const PATH_PATTERN: &str = r"^/g/(\d+)/([a-f0-9]+)/?$";
+        let [gid, token] = path_captures(value, PATH_PATTERN, "gallery")?;
+        Ok(GIDListItem(parse_number(value, &gid)?, token))
+    }
 }
 
-impl From<String> for GIDListItem {
+impl TryFrom<String> for GIDListItem {
+    type Error = EhError;
 
     /// 从画廊 URL 字符串转换为画廊 ID 及其令牌
-    fn from(value: String) -> Self {
-        const URL_PATTERN: &'static str =
-            r"^https://e[\-x]hentai.org/g/(?<gid>\d+)/(?<token>[a-f0-9]+)/?";
-        let regex = regex::Regex::new(URL_PATTERN).unwrap();
-        match regex.captures(&value) {
-            Some(captures) => {
-                let gid = captures["gid"].parse().unwrap();
-                let token = captures["token"].to_string();
-                GIDListItem(gid, token)
-            }
-            None => panic!("Invalid URL: {}", value),
-        }
+    fn try_from(value: String) -> EhResult<Self> {
+        value.parse()
+    }
+}
+
+impl TryFrom<&str> for GIDListItem {
+    type Error = EhError;
+
+    /// 从画廊 URL 字符串转换为画廊 ID 及其令牌
+    fn try_from(value: &str) -> EhResult<Self> {
+        value.parse()
     }
 }
 
+/// 解析 URL 并以正则表达式匹配其路径，返回各捕获组，不匹配时返回无效输入错误
+fn path_captures<const N: usize>(value: &str, pattern: &str, kind: &str) -> EhResult<[String; N]> {
+    let invalid = || EhError::invalid_input(format!("Invalid {} URL: {}", kind, value));
+    let url = Url::parse(value).map_err(|_| invalid())?;
+    let captures = regex(pattern)?.captures(url.path()).ok_or_else(invalid)?;
+    Ok(std::array::from_fn(|i| {
+        captures
+            .get(i + 1)
+            .map(|m| m.as_str().to_string())
+            .unwrap_or_default()
+    }))
+}
+
+/// 解析 URL 中的数字，超出范围时返回无效输入错误
+fn parse_number<T: FromStr>(value: &str, number: &str) -> EhResult<T> {
+    number
+        .parse()
+        .map_err(|_| EhError::invalid_input(format!("Invalid number in URL: {}", value)))
+}
+
 /// 通过画廊 ID 及其令牌检索元数据
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GalleryMetadataRequest {
@@ -49,6 +99,9 @@ pub struct GalleryMetadataRequest {
 }
 
 impl GalleryMetadataRequest {
+    /// 单次请求最多包含的画廊数
+    pub const MAX_GALLERIES: usize = 25;
+
     /// 将包含画廊 ID 及其令牌的列表转换为请求数据
     pub fn new(gidlist: Vec<GIDListItem>) -> Self {
         Self {
@@ -105,36 +158,124 @@ pub struct GalleryMetadata {
 }
 
 /// 请求画廊元数据的响应数据
+///
+/// API 会将无法获取的画廊以错误条目混在 `gmetadata` 中返回，这些条目被分离到 `errors`。
 #[derive(Debug, Clone, Deserialize)]
+#[serde(from = "RawGalleryMetadataResponse")]
 pub struct GalleryMetadataResponse {
     pub gmetadata: Vec<GalleryMetadata>,
+    pub errors: Vec<GalleryMetadataError>,
 }
 
+/// `gmetadata` 中的条目，画廊元数据或错误
 #[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum GalleryMetadataEntry {
+    Error(GalleryMetadataError),
+    Metadata(Box<GalleryMetadata>),
+}
+
+#[derive(Deserialize)]
+struct RawGalleryMetadataResponse {
+    gmetadata: Vec<GalleryMetadataEntry>,
+}
+
+impl From<RawGalleryMetadataResponse> for GalleryMetadataResponse {
+    fn from(raw: RawGalleryMetadataResponse) -> Self {
+        let mut response = GalleryMetadataResponse {
+            gmetadata: vec![],
+            errors: vec![],
+        };
+        for entry in raw.gmetadata {
+            match entry {
+                GalleryMetadataEntry::Error(err) => response.errors.push(err),
+                GalleryMetadataEntry::Metadata(metadata) => response.gmetadata.push(*metadata),
+            }
+        }
+        response
+    }
+}
+
+/// 无法获取元数据的画廊及原因
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct GalleryMetadataError {
     pub gid: i64,
     pub error: String,
 }
 
+/// 单个画廊的元数据请求结果
+pub type GalleryMetadataResult = Result<GalleryMetadata, GalleryMetadataError>;
+
 /// 页面列表，包含画廊 ID、页面令牌和页号
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PageListItem(i64, String, i32);
 
-impl From<String> for PageListItem {
+impl PageListItem {
+    /// 新建页面列表项
+    pub fn new(gid: i64, page_token: &str, page: i32) -> Self {
+        PageListItem(gid, page_token.into(), page)
+    }
+
+    /// 画廊 ID
+    pub fn gid(&self) -> i64 {
+        self.0
+    }
+
+    /// 页面令牌
+    pub fn page_token(&self) -> &str {
+        &self.1
+    }
+
+    /// 页号，从 1 开始
+    pub fn page(&self) -> i32 {
+        self.2
+    }
+}
+
+impl FromStr for PageListItem {
+    type Err = EhError;
+
+    /// 从页面 URL 解析画廊 ID、页面令牌和页号，不限制站点的域名
+    fn from_str(value: &str) -> EhResult<Self> {
+        const PATH_PATTERN: &str = r"^/s/([a-f0-9]+)/(\d+)-(\d+)/?$";
+        let [ptoken, gid, pnum] = path_captures(value, PATH_PATTERN, "page")?;
+        Ok(PageListItem(
+            parse_number(value, &gid)?,
+            ptoken,
+            parse_number(value, &pnum)?,
+        ))
+    }
+}
+
+impl TryFrom<String> for PageListItem {
+    type Error = EhError;
+
     /// 将包含画廊 ID、页面令牌和页号的 URL 字符串转换为请求数据
-    fn from(value: String) -> Self {
-        const URL_PATTERN: &'static str =
-            r"^https://e[\-x]hentai.org/s/(?<ptoken>[a-f0-9]+)/(?<gid>\d+)-(?<pnum>\d+)/?";
-        let regex = regex::Regex::new(URL_PATTERN).unwrap();
-        match regex.captures(&value) {
-            Some(captures) => {
-                let ptoken = captures["ptoken"].to_string();
-                let gid = captures["gid"].parse().unwrap();
-                let pnum = captures["pnum"].parse().unwrap();
-                PageListItem(gid, ptoken, pnum)
-            }
-            None => panic!("Invalid URL: {}", value),
-        }
+    fn try_from(value: String) -> EhResult<Self> {
+        value.parse()
+    }
+}
+
+impl TryFrom<&str> for PageListItem {
+    type Error = EhError;
+
+    /// 将包含画廊 ID、页面令牌和页号的 URL 字符串转换为请求数据
+    fn try_from(value: &str) -> EhResult<Self> {
+        value.parse()
+    }
+}
+
+impl From<(i64, &str, i32)> for PageListItem {
+    /// 从画廊 ID、页面令牌和页号转换
+    fn from((gid, page_token, page): (i64, &str, i32)) -> Self {
+        PageListItem::new(gid, page_token, page)
+    }
+}
+
+impl From<(i64, String, i32)> for PageListItem {
+    /// 从画廊 ID、页面令牌和页号转换
+    fn from((gid, page_token, page): (i64, String, i32)) -> Self {
+        PageListItem(gid, page_token, page)
     }
 }
 
@@ -148,6 +289,9 @@ pub struct GalleryTokensRequest {
 }
 
 impl GalleryTokensRequest {
+    /// 单次请求最多包含的页面数
+    pub const MAX_PAGES: usize = 25;
+
     /// 将包含页面列表的请求数据转换为请求数据
     pub fn new(pagelist: Vec<PageListItem>) -> Self {
         Self {
@@ -165,17 +309,66 @@ pub struct TokenListItem {
 }
 
 /// 反查画廊令牌的响应数据
+///
+/// 无法反查的页面以错误条目混在 `tokenlist` 中返回，这些条目被分离到 `errors`。
 #[derive(Debug, Clone, Deserialize)]
+#[serde(from = "RawGalleryTokenResponse")]
 pub struct GalleryTokenResponse {
     pub tokenlist: Vec<TokenListItem>,
+    pub errors: Vec<GalleryTokenError>,
+}
+
+/// `tokenlist` 中的条目，画廊令牌或错误
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum TokenListEntry {
+    Error(GalleryTokenError),
+    Token(TokenListItem),
 }
 
+#[derive(Deserialize)]
+struct RawGalleryTokenResponse {
+    tokenlist: Vec<TokenListEntry>,
+}
+
+impl From<RawGalleryTokenResponse> for GalleryTokenResponse {
+    fn from(raw: RawGalleryTokenResponse) -> Self {
+        let mut response = GalleryTokenResponse {
+            tokenlist: vec![],
+            errors: vec![],
+        };
+        for entry in raw.tokenlist {
+            match entry {
+                TokenListEntry::Error(err) => response.errors.push(err),
+                TokenListEntry::Token(item) => response.tokenlist.push(item),
+            }
+        }
+        response
+    }
+}
+
+/// 无法反查令牌的画廊及原因
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct GalleryTokenError {
+    pub gid: i64,
+    pub error: String,
+}
+
+/// 单个页面的令牌反查结果
+pub type GalleryTokenResult = Result<GIDListItem, GalleryTokenError>;
+
 #[cfg(test)]
 mod tests {
-    use reqwest::Url;
+    use std::sync::Arc;
 
     use crate::{
-        client::{client::EhClient, config::EhClientConfig, proxy::EhClientProxy},
+        client::{
+            client::EhClient,
+            config::EhClientConfig,
+            endpoints::EhEndpoints,
+            proxy::EhClientProxy,
+            transport::{EhResponse, FixtureTransport},
+        },
         dto::{
             api::{
                 GIDListItem, GalleryMetadataRequest, GalleryMetadataResponse, GalleryTokenResponse,
@@ -183,40 +376,55 @@ mod tests {
             },
             site::Site,
         },
+        error::EhResult,
     };
 
     #[test]
     fn test_gid_list_item() {
-        let item = GIDListItem::from("https://e-hentai.org/g/2231376/a7584a5932/".to_string());
+        let item = GIDListItem::try_from("https://e-hentai.org/g/2231376/a7584a5932/".to_string())
+            .unwrap();
         assert_eq!(item.0, 2231376);
         assert_eq!(item.1, "a7584a5932".to_string());
+        assert!("https://e-hentai.org/s/40bc07a79a/618395-11"
+            .parse::<GIDListItem>()
+            .is_err());
     }
 
     #[test]
     fn test_page_list_item() {
-        let item = PageListItem::from("https://e-hentai.org/s/40bc07a79a/618395-11".to_string());
+        let item =
+            PageListItem::try_from("https://e-hentai.org/s/40bc07a79a/618395-11".to_string())
+                .unwrap();
         assert_eq!(item.0, 618395);
         assert_eq!(item.1, "40bc07a79a".to_string());
         assert_eq!(item.2, 11);
+        assert_eq!(item, PageListItem::from((618395, "40bc07a79a", 11)));
+        assert!(PageListItem::try_from("https://e-hentai.org/g/2231376/a7584a5932/").is_err());
+        assert!("https://e-hentai.org/s/40bc07a79a/99999999999999999999-1"
+            .parse::<PageListItem>()
+            .is_err());
     }
 
     #[tokio::test]
+    #[ignore = "需要通过本地代理访问站点"]
     async fn test_gallery_metadata_request() {
         let proxy = EhClientProxy::new("http", "127.0.0.1", 7897);
         let config = EhClientConfig {
             site: Site::Eh,
             proxy: Some(proxy),
             auth: None,
+            ..Default::default()
         };
         let client = EhClient::new(config);
-        let url = Url::parse("https://api.e-hentai.org/api.php").unwrap();
+        let url = EhEndpoints::default().api().unwrap();
         // let body =
         //     GalleryMetadataRequest::new(vec![GIDListItem(2465890, "8af9a35448".to_string())]);
-        let body = GalleryMetadataRequest::new(vec![GIDListItem::from(
+        let body = GalleryMetadataRequest::new(vec![GIDListItem::try_from(
             "https://e-hentai.org/g/2791585/3e7e1c7107/".to_string(),
-        )]);
+        )
+        .unwrap()]);
         let body = serde_json::to_string(&body).unwrap();
-        let res: Result<GalleryMetadataResponse, String> = client.post_json(url, body).await;
+        let res: EhResult<GalleryMetadataResponse> = client.post_json(url, body).await;
         let res = res.unwrap();
         assert_eq!(res.gmetadata.len(), 1);
         assert_eq!(res.gmetadata[0].gid, 2791585);
@@ -224,23 +432,79 @@ mod tests {
     }
 
     #[tokio::test]
+    async fn test_gallery_metadata_fixture() {
+        let url = EhEndpoints::default().api().unwrap();
+        let metadata = serde_json::json!({
+            "gid": 2791585, "token": "3e7e1c7107", "archiver_key": null,
+            "title": "title", "title_jpn": "", "category": "Manga",
+            "thumb": "", "uploader": "uploader", "posted": "1700000000",
+            "filecount": "20", "filesize": 1024, "expunged": false,
+            "rating": "4.5", "torrentcount": "0", "torrents": [],
+            "tags": ["language:chinese"], "parent_gid": null, "parent_key": null,
+            "first_gid": null, "first_key": null
+        });
+        let body = serde_json::json!({ "gmetadata": [metadata] }).to_string();
+        let transport =
+            Arc::new(FixtureTransport::new().post(url.as_str(), EhResponse::new(200, body)));
+        let client = EhClient::new_with_transport(EhClientConfig::default(), transport.clone());
+        let body = GalleryMetadataRequest::new(vec![GIDListItem::try_from(
+            "https://e-hentai.org/g/2791585/3e7e1c7107/".to_string(),
+        )
+        .unwrap()]);
+        let body = serde_json::to_string(&body).unwrap();
+        let res: GalleryMetadataResponse = client.post_json(url, body).await.unwrap();
+        assert_eq!(res.gmetadata.len(), 1);
+        assert_eq!(res.gmetadata[0].gid, 2791585);
+        assert_eq!(res.gmetadata[0].token, "3e7e1c7107".to_string());
+        assert!(res.errors.is_empty());
+    }
+
+    #[tokio::test]
+    #[ignore = "需要通过本地代理访问站点"]
     async fn test_gallery_token_request() {
         let proxy = EhClientProxy::new("http", "127.0.0.1", 7897);
         let config = EhClientConfig {
             site: Site::Eh,
             proxy: Some(proxy),
             auth: None,
+            ..Default::default()
         };
         let client = EhClient::new(config);
-        let url = Url::parse("https://api.e-hentai.org/api.php").unwrap();
-        let body = GalleryTokensRequest::new(vec![PageListItem::from(
+        let url = EhEndpoints::default().api().unwrap();
+        let body = GalleryTokensRequest::new(vec![PageListItem::try_from(
             "https://e-hentai.org/s/d384d63ec0/2519745-8".to_string(),
-        )]);
+        )
+        .unwrap()]);
         let body = serde_json::to_string(&body).unwrap();
-        let res: Result<GalleryTokenResponse, String> = client.post_json(url, body).await;
+        let res: EhResult<GalleryTokenResponse> = client.post_json(url, body).await;
         let res = res.unwrap();
         assert_eq!(res.tokenlist.len(), 1);
         assert_eq!(res.tokenlist[0].gid, 2519745);
         assert_eq!(res.tokenlist[0].token, "76939e430f".to_string());
     }
+
+    #[tokio::test]
+    async fn test_gallery_token_fixture() {
+        let url = EhEndpoints::default().api().unwrap();
+        let transport = Arc::new(FixtureTransport::new().post(
+            url.as_str(),
+            EhResponse::new(
+                200,
+                r#"{"tokenlist":[{"gid":2519745,"token":"76939e430f"}]}"#,
+            ),
+        ));
+        let client = EhClient::new_with_transport(EhClientConfig::default(), transport.clone());
+        let body = GalleryTokensRequest::new(vec![PageListItem::try_from(
+            "https://e-hentai.org/s/d384d63ec0/2519745-8".to_string(),
+        )
+        .unwrap()]);
+        let body = serde_json::to_string(&body).unwrap();
+        let res: GalleryTokenResponse = client.post_json(url, body).await.unwrap();
+        assert_eq!(res.tokenlist[0].gid, 2519745);
+        assert_eq!(res.tokenlist[0].token, "76939e430f".to_string());
+        assert_eq!(
+            transport.requests()[0].body,
+            r#"{"method":"gtoken","pagelist":[[2519745,"d384d63ec0",8]]}"#
+        );
+    }
 }
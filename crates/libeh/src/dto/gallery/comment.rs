@@ -2,16 +2,19 @@ use chrono::{DateTime, NaiveDateTime, Utc};
 use scraper::Html;
 use serde::{Deserialize, Serialize};
 
-use crate::utils::{
-    regex::regex,
-    scraper::{parse_to, selector, text_content},
+use crate::{
+    error::{EhError, EhResult},
+    utils::{
+        regex::regex,
+        scraper::{parse_to, selector, text_content},
+    },
 };
 
-const PATTERN_COMMENT_TIME: &'static str = r"Posted on (.+) by:";
-const PATTERN_COMMENT_ID: &'static str = r"comment_score_(\d+)";
-const PATTERN_COMMENT_VOTE_BASE: &'static str = r"Base ([\+\-]?\d+)";
-const PATTERN_COMMENT_VOTE: &'static str = r"(?<user>.+) (?<score>[\+\-]?\d+)$";
-const PATTERN_COMMENT_VOTE_MORE: &'static str = r"and (\d+) more...";
+const PATTERN_COMMENT_TIME: &str = r"Posted on (.+) by:";
+const PATTERN_COMMENT_ID: &str = r"comment_score_(\d+)";
+const PATTERN_COMMENT_VOTE_BASE: &str = r"Base ([\+\-]?\d+)";
+const PATTERN_COMMENT_VOTE: &str = r"(?<user>.+) (?<score>[\+\-]?\d+)$";
+const PATTERN_COMMENT_VOTE_MORE: &str = r"and (\d+) more...";
 
 /// 画廊评论
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -67,6 +70,18 @@ impl GalleryCommentVoteState {
     }
 }
 
+impl Default for GalleryCommentVoteState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Default for GalleryComment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl GalleryComment {
     pub fn new() -> GalleryComment {
         GalleryComment {
@@ -86,7 +101,8 @@ impl GalleryComment {
     }
 
     /// 解析画廊评论
-    pub fn parse(d: &Html) -> Result<Vec<GalleryComment>, String> {
+    pub fn parse(d: &Html) -> EhResult<Vec<GalleryComment>> {
+        let _span = tracing::debug_span!("eh_parse", kind = "comments").entered();
         let r_comment_time = regex(PATTERN_COMMENT_TIME)?;
         let r_comment_id = regex(PATTERN_COMMENT_ID)?;
         let r_comment_vote_base = regex(PATTERN_COMMENT_VOTE_BASE)?;
@@ -107,9 +123,10 @@ impl GalleryComment {
                         gc.time = match r_comment_time.captures(&text) {
                             Some(caps) => Self::parse_comment_time(&caps[1])?,
                             None => {
-                                return Err(format!(
-                                    "Failed to parse comment: {}",
-                                    "No comment datetime."
+                                return Err(EhError::parse_selector(
+                                    "comment",
+                                    "div.c3",
+                                    "No comment datetime.",
                                 ))
                             }
                         };
@@ -121,95 +138,87 @@ impl GalleryComment {
                                 gc.user = text;
                             }
                             None => {
-                                return Err(format!(
-                                    "Failed to parse comment: {}",
-                                    "No comment user."
+                                return Err(EhError::parse_selector(
+                                    "comment",
+                                    "a",
+                                    "No comment user.",
                                 ))
                             }
                         };
                     }
-                    None => return Err(format!("Failed to parse comment: {}", "Invalid comment.")),
+                    None => {
+                        return Err(EhError::parse_selector(
+                            "comment",
+                            "div.c3",
+                            "Invalid comment.",
+                        ))
+                    }
                 }
                 // 解析评论分数
                 let s = selector(r#"span[id^="comment_score_"]"#)?;
-                match c1.select(&s).next() {
-                    Some(comment_score) => {
-                        let text = comment_score.attr("id");
-                        if let Some(text) = text {
-                            match r_comment_id.captures(&text) {
-                                Some(caps) => {
-                                    gc.id = Some(parse_to::<i64>(&caps[1])?);
-                                }
-                                None => {
-                                    return Err(format!(
-                                        "Failed to parse comment: {}",
-                                        "Invalid comment id."
-                                    ));
-                                }
+                if let Some(comment_score) = c1.select(&s).next() {
+                    let text = comment_score.attr("id");
+                    if let Some(text) = text {
+                        match r_comment_id.captures(text) {
+                            Some(caps) => {
+                                gc.id = Some(parse_to::<i64>(&caps[1])?);
+                            }
+                            None => {
+                                return Err(EhError::parse("comment", "Invalid comment id."));
                             }
                         }
-                        let text = text_content(comment_score.text());
-                        gc.score = parse_to::<i64>(&text)?;
                     }
-                    None => {}
+                    let text = text_content(comment_score.text());
+                    gc.score = parse_to::<i64>(&text)?;
                 }
                 // 解析评论内容
                 let s = selector(r#"div.c6[id^="comment_""#)?;
-                match c1.select(&s).next() {
-                    Some(c6) => {
-                        let text = c6.inner_html().trim().to_string();
-                        gc.comment = text;
-                    }
-                    None => {}
+                if let Some(c6) = c1.select(&s).next() {
+                    let text = c6.inner_html().trim().to_string();
+                    gc.comment = text;
                 }
                 // 解析评论评分情况
                 let s = selector(r#"div.c7[id^="cvotes_""#)?;
-                match c1.select(&s).next() {
-                    Some(c7) => {
-                        let mut c7text = c7.text();
-                        if let Some(base) = c7text.next() {
-                            match r_comment_vote_base.captures(&base) {
+                if let Some(c7) = c1.select(&s).next() {
+                    let mut c7text = c7.text();
+                    if let Some(base) = c7text.next() {
+                        match r_comment_vote_base.captures(base) {
+                            Some(caps) => {
+                                let score = parse_to::<i64>(&caps[1])?;
+                                gc.vote_state.base = score;
+                            }
+                            None => {
+                                return Err(EhError::parse(
+                                    "comment vote",
+                                    "Invalid comment vote base.",
+                                ));
+                            }
+                        }
+                        let s = selector("span")?;
+                        for vote in c7.select(&s) {
+                            let text = text_content(vote.text());
+                            match r_comment_vote.captures(text.trim()) {
                                 Some(caps) => {
-                                    let score = parse_to::<i64>(&caps[1])?;
-                                    gc.vote_state.base = score;
+                                    let user = caps[1].to_string();
+                                    let score = parse_to::<i64>(&caps[2])?;
+                                    let vote = GalleryCommentVote { user, score };
+                                    gc.vote_state.votes.push(vote);
                                 }
                                 None => {
-                                    return Err(format!(
-                                        "Failed to parse comment vote: {}",
-                                        "Invalid comment vote base."
+                                    return Err(EhError::parse(
+                                        "comment vote",
+                                        "Invalid comment vote.",
                                     ));
                                 }
                             }
-                            let s = selector("span")?;
-                            for vote in c7.select(&s) {
-                                let text = text_content(vote.text());
-                                match r_comment_vote.captures(text.trim()) {
-                                    Some(caps) => {
-                                        let user = caps[1].to_string();
-                                        let score = parse_to::<i64>(&caps[2])?;
-                                        let vote = GalleryCommentVote { user, score };
-                                        gc.vote_state.votes.push(vote);
-                                    }
-                                    None => {
-                                        return Err(format!(
-                                            "Failed to parse comment vote: {}",
-                                            "Invalid comment vote."
-                                        ));
-                                    }
-                                }
-                            }
-                            if let Some(more) = c7text.last() {
-                                match r_comment_vote_more.captures(&more) {
-                                    Some(caps) => {
-                                        let more = parse_to::<i64>(&caps[1])?;
-                                        gc.vote_state.more = more;
-                                    }
-                                    None => {}
-                                }
+                        }
+                        if let Some(more) = c7text.last() {
+                            if let Some(caps) = r_comment_vote_more.captures(more) {
+                                let more = parse_to::<i64>(&caps[1])?;
+                                gc.vote_state.more = more;
                             }
                         }
                     }
-                    None => {}
                 }
                 comments.push(gc);
             }
@@ -218,10 +227,10 @@ impl GalleryComment {
     }
 
     /// 解析评论时间
-    fn parse_comment_time(text: &str) -> Result<DateTime<Utc>, String> {
+    fn parse_comment_time(text: &str) -> EhResult<DateTime<Utc>> {
         match NaiveDateTime::parse_from_str(text, "%d %B %Y, %H:%M") {
             Ok(date) => Ok(date.and_utc()),
-            Err(err) => Err(format!("Failed to parse comment time: {}", err)),
+            Err(err) => Err(EhError::parse("comment time", err.to_string())),
         }
     }
 }
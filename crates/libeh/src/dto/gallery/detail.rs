@@ -5,10 +5,13 @@ use scraper::{Element, Html};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    dto::{gallery::{
-        category::Category, comment::GalleryComment, info::GalleryInfo,
-        preview::GalleryPreview,
-    }, keyword::Keyword},
+    dto::{
+        gallery::{
+            category::Category, comment::GalleryComment, info::GalleryInfo, preview::GalleryPreview,
+        },
+        keyword::Keyword,
+    },
+    error::{EhError, EhResult, SiteError},
     url::gallery::GalleryBuilder,
     utils::{
         regex::regex,
@@ -16,43 +19,42 @@ use crate::{
     },
 };
 
-const PATTERN_ERROR: &'static str = r#"<div class="d">\n<p>([^<]+)</p>"#;
-const PATTERN_DETAIL: &'static str = r#"var gid = (?<gid>\d+);\s+?var token = "(?<token>[a-f0-9]+)";\s+?var apiuid = (?<apiuid>-?\d+);\s+?var apikey = "(?<apikey>[a-f0-9]+)";"#;
+const PATTERN_ERROR: &str = r#"<div class="d">\n<p>([^<]+)</p>"#;
+const PATTERN_DETAIL: &str = r#"var gid = (?<gid>\d+);\s+?var token = "(?<token>[a-f0-9]+)";\s+?var apiuid = (?<apiuid>-?\d+);\s+?var apikey = "(?<apikey>[a-f0-9]+)";"#;
 #[allow(dead_code)]
-const PATTERN_TORRENT: &'static str = r#"<a[^<>]*onclick="return popUp\('(?<link>[^']+)'[^)]+\)">Torrent Download \((<?count>\d+)\)</a>"#;
-const PATTERN_TORRENT_ONCLICK: &'static str = r#"return popUp\('(?<link>[^']+)'[^)]+\)"#;
-const PATTERN_TORRENT_COUNT: &'static str = r#"Torrent Download \((?<count>\d+)\)"#;
+const PATTERN_TORRENT: &str = r#"<a[^<>]*onclick="return popUp\('(?<link>[^']+)'[^)]+\)">Torrent Download \((<?count>\d+)\)</a>"#;
+const PATTERN_TORRENT_ONCLICK: &str = r#"return popUp\('(?<link>[^']+)'[^)]+\)"#;
+const PATTERN_TORRENT_COUNT: &str = r#"Torrent Download \((?<count>\d+)\)"#;
 #[allow(dead_code)]
-const PATTERN_ARCHIVE: &'static str =
+const PATTERN_ARCHIVE: &str =
     r#"<a[^<>]*onclick="return popUp\('([^']+)'[^)]+\)">Archive Download</a>"#;
-const PATTERN_ARCHIVE_ONCLICK: &'static str = r#"return popUp\('(?<link>[^']+)'[^)]+\)"#;
-const PATTERN_COVER: &'static str =
+const PATTERN_ARCHIVE_ONCLICK: &str = r#"return popUp\('(?<link>[^']+)'[^)]+\)"#;
+const PATTERN_COVER: &str =
     r#"width:(?<width>\d+)px; height:(?<height>\d+)px.+?url\((?<link>.+?)\)"#;
 #[allow(dead_code)]
-const PATTERN_TAG_GROUP: &'static str =
+const PATTERN_TAG_GROUP: &str =
     r#"<tr><td[^<>]+>([\w\s]+):</td><td>(?:<div[^<>]+><a[^<>]+>[\w\s]+</a></div>)+</td></tr>"#;
 #[allow(dead_code)]
-const PATTERN_TAG: &'static str = r#"<div[^<>]+><a[^<>]+>([\w\s]+)</a></div>"#;
+const PATTERN_TAG: &str = r#"<div[^<>]+><a[^<>]+>([\w\s]+)</a></div>"#;
 #[allow(dead_code)]
-const PATTERN_COMMENT: &'static str = r#"<div class=\"c3\">Posted on ([^<>]+) by: &nbsp; <a[^<>]+>([^<>]+)</a>.+?<div class=\"c6\"[^>]*>(.+?)</div><div class=\"c[78]\""#;
+const PATTERN_COMMENT: &str = r#"<div class=\"c3\">Posted on ([^<>]+) by: &nbsp; <a[^<>]+>([^<>]+)</a>.+?<div class=\"c6\"[^>]*>(.+?)</div><div class=\"c[78]\""#;
 #[allow(dead_code)]
-const PATTERN_PAGES: &'static str =
-    r#"<tr><td[^<>]*>Length:</td><td[^<>]*>([\\d,]+) pages</td></tr>"#;
-const PATTERN_PAGES_TEXT: &'static str = r"(?<length>\d+) pages";
-const PATTERN_FAVORITE_COUNT: &'static str = r"(?<count>\d+) times";
-const PATTERN_NEW_VERSION_DATETIME: &'static str = r"added (?<datetime>\d+-\d+-\d+ \d+:\d+)";
+const PATTERN_PAGES: &str = r#"<tr><td[^<>]*>Length:</td><td[^<>]*>([\\d,]+) pages</td></tr>"#;
+const PATTERN_PAGES_TEXT: &str = r"(?<length>\d+) pages";
+const PATTERN_FAVORITE_COUNT: &str = r"(?<count>\d+) times";
+const PATTERN_NEW_VERSION_DATETIME: &str = r"added (?<datetime>\d+-\d+-\d+ \d+:\d+)";
 #[allow(dead_code)]
-const PATTERN_PREVIEW_PAGES: &'static str =
+const PATTERN_PREVIEW_PAGES: &str =
     r#"<td[^>]+><a[^>]+>([\\d,]+)</a></td><td[^>]+>(?:<a[^>]+>)?&gt;(?:</a>)?</td>"#;
 #[allow(dead_code)]
-const PATTERN_NORMAL_PREVIEW: &'static str = r#"<div class=\"gdtm\"[^<>]*><div[^<>]*width:(\\d+)[^<>]*height:(\\d+)[^<>]*\\((.+?)\\)[^<>]*-(\\d+)px[^<>]*><a[^<>]*href=\"(.+?)\"[^<>]*><img alt=\"([\\d,]+)\""#;
+const PATTERN_NORMAL_PREVIEW: &str = r#"<div class=\"gdtm\"[^<>]*><div[^<>]*width:(\\d+)[^<>]*height:(\\d+)[^<>]*\\((.+?)\\)[^<>]*-(\\d+)px[^<>]*><a[^<>]*href=\"(.+?)\"[^<>]*><img alt=\"([\\d,]+)\""#;
 #[allow(dead_code)]
-const PATTERN_LARGE_PREVIEW: &'static str =
+const PATTERN_LARGE_PREVIEW: &str =
     r#"<div class=\"gdtl\".+?<a href=\"(.+?)\"><img alt=\"([\\d,]+)\".+?src=\"(.+?)\""#;
 
-const OFFENSIVE_STRING: &'static str =
+const OFFENSIVE_STRING: &str =
             "<p>(And if you choose to ignore this warning, you lose all rights to complain about it in the future.)</p>";
-const PINING_STRING: &'static str = "<p>This gallery is pining for the fjords.</p>";
+const PINING_STRING: &str = "<p>This gallery is pining for the fjords.</p>";
 
 /// 画廊详情，由画廊详情页面解析获得
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -100,7 +102,7 @@ pub struct GalleryNewVersion {
 }
 
 impl FromStr for GalleryNewVersion {
-    type Err = String;
+    type Err = EhError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let builder = GalleryBuilder::parse(s.into())?;
@@ -139,16 +141,18 @@ impl Default for GalleryDetail {
 
 impl GalleryDetail {
     /// 从 HTML 解析画廊详情
-    pub fn parse(html: String) -> Result<Self, String> {
-        if html.contains(OFFENSIVE_STRING) || html.contains(PINING_STRING) {
-            return Err(format!(
-                "Failed to parse gallery detail: {}",
-                "Offensive or pining."
-            ));
+    pub fn parse(html: String) -> EhResult<Self> {
+        let _span =
+            tracing::debug_span!("eh_parse", kind = "gallery", bytes = html.len()).entered();
+        if html.contains(OFFENSIVE_STRING) {
+            return Err(SiteError::Offensive.into());
+        }
+        if html.contains(PINING_STRING) {
+            return Err(SiteError::Pining.into());
         }
         let r = regex(PATTERN_ERROR)?;
-        if r.is_match(&html) {
-            return Err(format!("Failed to parse gallery detail: {}", "Page error."));
+        if let Some(caps) = r.captures(&html) {
+            return Err(SiteError::Message(caps[1].trim().to_string()).into());
         }
 
         let mut gallery_detail = Self::default();
@@ -160,11 +164,11 @@ impl GalleryDetail {
     }
 
     /// 解析画廊详情
-    fn parse_detail(gd: &mut Self, d: &Html, html: String) -> Result<(), String> {
+    fn parse_detail(gd: &mut Self, d: &Html, html: String) -> EhResult<()> {
         let r = regex(PATTERN_DETAIL)?;
         let caps = match r.captures(&html) {
             Some(caps) => caps,
-            None => return Err(format!("Failed to parse gallery detail: {}", "No detail.")),
+            None => return Err(EhError::parse("gallery detail", "No detail.")),
         };
         // GID
         let gid = parse_to::<i64>(&caps["gid"])?;
@@ -184,7 +188,7 @@ impl GalleryDetail {
         if let Some(torrent_ele) = d.select(&s).next() {
             if let Some(link) = torrent_ele.attr("onclick") {
                 let r = regex(PATTERN_TORRENT_ONCLICK)?;
-                if let Some(caps) = r.captures(&link) {
+                if let Some(caps) = r.captures(link) {
                     let link = caps["link"].to_string();
                     gd.torrent_url = link;
                 }
@@ -202,7 +206,7 @@ impl GalleryDetail {
         if let Some(archive_ele) = d.select(&s).next() {
             if let Some(link) = archive_ele.attr("onclick") {
                 let r = regex(PATTERN_ARCHIVE_ONCLICK)?;
-                if let Some(caps) = r.captures(&link) {
+                if let Some(caps) = r.captures(link) {
                     let link = caps["link"].to_string();
                     gd.archive_url = link;
                 }
@@ -248,7 +252,7 @@ impl GalleryDetail {
         }
 
         // GalleryInfo
-        Self::parse_detail_info(gd, &d)?;
+        Self::parse_detail_info(gd, d)?;
 
         // Rating Count
         let s = selector("#rating_count")?;
@@ -263,8 +267,8 @@ impl GalleryDetail {
         let s = selector("#rating_label")?;
         if let Some(rating_ele) = d.select(&s).next() {
             let text = text_content(rating_ele.text());
-            if let Some(value) = text.split(" ").last() {
-                if let Ok(value) = parse_to::<f32>(&value) {
+            if let Some(value) = text.split(' ').next_back() {
+                if let Ok(value) = parse_to::<f32>(value) {
                     gd.info.rating = value;
                 }
             }
@@ -283,16 +287,16 @@ impl GalleryDetail {
         }
 
         // 解析画廊新版本
-        Self::parse_new_version(gd, &d)?;
+        Self::parse_new_version(gd, d)?;
 
         // 解析画廊标签
-        Self::parse_tag_groups(gd, &d)?;
+        Self::parse_tag_groups(gd, d)?;
 
         Ok(())
     }
 
     /// 解析当前画廊是否有新版本
-    fn parse_new_version(gd: &mut Self, d: &Html) -> Result<(), String> {
+    fn parse_new_version(gd: &mut Self, d: &Html) -> EhResult<()> {
         let s = selector("#gnd > a")?;
         let r = regex(PATTERN_NEW_VERSION_DATETIME)?;
         for ele in d.select(&s) {
@@ -318,7 +322,7 @@ impl GalleryDetail {
     }
 
     /// 解析画廊元数据
-    fn parse_detail_info(gd: &mut Self, d: &Html) -> Result<(), String> {
+    fn parse_detail_info(gd: &mut Self, d: &Html) -> EhResult<()> {
         // 选择器：获取表格各行
         let s = selector("#gdd > table > tbody > tr")?;
         let selected = d.select(&s);
@@ -347,10 +351,7 @@ impl GalleryDetail {
                         }
                         s if s.starts_with("Visible") => {
                             // 设置可见性
-                            gd.visible = match value_text.trim() {
-                                s if s.starts_with("Yes") => true,
-                                _ => false,
-                            }
+                            gd.visible = value_text.trim().starts_with("Yes")
                         }
                         s if s.starts_with("Language") => {
                             // 设置语言
@@ -377,9 +378,9 @@ impl GalleryDetail {
                                     match r.captures(&value_text) {
                                         Some(caps) => parse_to::<i64>(&caps["count"])?,
                                         None => {
-                                            return Err(format!(
-                                                "Failed to parse favorite count: {}",
-                                                "No count."
+                                            return Err(EhError::parse(
+                                                "favorite count",
+                                                "No count.",
                                             ))
                                         }
                                     }
@@ -395,19 +396,19 @@ impl GalleryDetail {
     }
 
     /// 解析封面样式中的链接
-    fn parse_cover_style(style: &str) -> Result<String, String> {
+    fn parse_cover_style(style: &str) -> EhResult<String> {
         let r = regex(PATTERN_COVER)?;
         match r.captures(style) {
             Some(caps) => {
                 let cover = caps["link"].to_string();
                 Ok(cover)
             }
-            None => Err(format!("Failed to parse cover style: {}", "No cover.")),
+            None => Err(EhError::parse("cover style", "No cover.")),
         }
     }
 
     /// 解析标签组
-    fn parse_tag_groups(gd: &mut Self, d: &Html) -> Result<(), String> {
+    fn parse_tag_groups(gd: &mut Self, d: &Html) -> EhResult<()> {
         // 选择器：选择包含标签组的tr元素
         let s = selector("#taglist tr")?;
         // 遍历每个tr元素
@@ -8,3 +8,5 @@ pub mod detail;
 pub mod info;
 /// 画廊预览
 pub mod preview;
+/// 图片页面及解析器
+pub mod show;
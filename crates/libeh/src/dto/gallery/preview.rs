@@ -1,14 +1,17 @@
 use scraper::Html;
 use serde::{Deserialize, Serialize};
 
-use crate::utils::{
-    regex::regex,
-    scraper::{parse_to, selector, text_content},
+use crate::{
+    error::{EhError, EhResult},
+    utils::{
+        regex::regex,
+        scraper::{parse_to, selector, text_content},
+    },
 };
 
-const PATTERN_TOTAL_PAGES: &'static str =
+const PATTERN_TOTAL_PAGES: &str =
     r"Showing ((\d+)(,\d+)*) - ((\d+)(,\d+)*) of (?<total>(\d+)(,\d+)*) images";
-const PATTERN_STYLE: &'static str =
+const PATTERN_STYLE: &str =
     r"background:transparent url\((?<url>[^\(\)]+)\) (?<x>-?\d+)(px)? (?<y>-?\d+)(px)?";
 
 /// GalleryPreview 结构体定义了一个画廊的预览信息
@@ -49,7 +52,8 @@ impl Default for GalleryPreview {
 
 impl GalleryPreview {
     /// 解析HTML内容，获取图库预览的信息
-    pub fn parse(d: &Html) -> Result<Self, String> {
+    pub fn parse(d: &Html) -> EhResult<Self> {
+        let _span = tracing::debug_span!("eh_parse", kind = "preview").entered();
         // 解析总页数
         let total = Self::parse_total_page_count(d)?;
         // 解析总预览集数
@@ -69,33 +73,34 @@ impl GalleryPreview {
     }
 
     /// 解析画廊需要预览的总页数
-    fn parse_total_page_count(d: &Html) -> Result<i64, String> {
+    fn parse_total_page_count(d: &Html) -> EhResult<i64> {
         let r = regex(PATTERN_TOTAL_PAGES)?;
         let s = selector("div.gtb > p.gpc")?;
         let td = match d.select(&s).next() {
             Some(td) => td,
             None => {
-                return Err(format!(
-                    "Failed to parse total preview pages: {}",
-                    "No total preview pages."
+                return Err(EhError::parse_selector(
+                    "total preview pages",
+                    "div.gtb > p.gpc",
+                    "No total preview pages.",
                 ))
             }
         };
         let text = text_content(td.text());
         match r.captures(&text) {
             Some(caps) => {
-                let total = parse_to::<i64>(&caps["total"].replace(",", ""))?;
+                let total = parse_to::<i64>(&caps["total"].replace(',', ""))?;
                 Ok(total)
             }
-            None => Err(format!(
-                "Failed to parse total preview pages: {}",
-                "No total preview pages."
+            None => Err(EhError::parse(
+                "total preview pages",
+                "No total preview pages.",
             )),
         }
     }
 
     /// 获取画廊总共需要解析的预览分页数量
-    fn parse_total_preview_set(d: &Html) -> Result<i64, String> {
+    fn parse_total_preview_set(d: &Html) -> EhResult<i64> {
         let s = selector("div.gtb > table.ptt tr > td:nth-last-child(2)")?;
         match d.select(&s).next() {
             Some(td) => {
@@ -103,15 +108,16 @@ impl GalleryPreview {
                 let value = parse_to::<i64>(&text)?;
                 Ok(value)
             }
-            None => Err(format!(
-                "Failed to parse total preview pages: {}",
-                "No total preview pages."
+            None => Err(EhError::parse_selector(
+                "total preview set",
+                "div.gtb > table.ptt tr > td:nth-last-child(2)",
+                "No total preview set.",
             )),
         }
     }
 
     /// 解析画廊预览页面列表
-    pub fn parse_preview_pages(d: &Html) -> Result<Vec<GalleryPreviewPage>, String> {
+    pub fn parse_preview_pages(d: &Html) -> EhResult<Vec<GalleryPreviewPage>> {
         // 初始化正则表达式，用于从style属性中提取信息。
         let r = regex(PATTERN_STYLE)?;
         // 定义选择器，用于定位预览页面的div元素。
@@ -126,13 +132,13 @@ impl GalleryPreview {
                 Some(style) => style,
                 None => continue,
             };
-            let caps = match r.captures(&style) {
+            let caps = match r.captures(style) {
                 Some(caps) => caps,
                 None => continue,
             };
             let url = caps["url"].to_string();
-            let offset_x = parse_to::<i64>(&caps["x"].to_string())?;
-            let offset_y = parse_to::<i64>(&caps["y"].to_string())?;
+            let offset_x = parse_to::<i64>(&caps["x"])?;
+            let offset_y = parse_to::<i64>(&caps["y"])?;
             let link = match div.select(&s_a).next() {
                 Some(a) => a,
                 None => continue,
@@ -0,0 +1,274 @@
+use scraper::Html;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    dto::api::GIDListItem,
+    error::{EhError, EhResult},
+    utils::{
+        regex::regex,
+        scraper::{parse_to, selector, text_content},
+    },
+};
+
+const PATTERN_IMAGE_INFO: &str = r"^(?<name>.+?) :: (?<width>\d+) x (?<height>\d+) :: (?<size>.+)$";
+const PATTERN_ORIGINAL: &str =
+    r"Download original (?<width>\d+) x (?<height>\d+) (?<size>.+?) source";
+const PATTERN_NL: &str = r"nl\('(?<nl>[^']+)'\)";
+const PATTERN_SHOWKEY: &str = r#"var showkey\s*=\s*"(?<showkey>[^"]+)";"#;
+
+/// 图片页面（`/s/{page_token}/{gid}-{page}`），由图片页面解析获得
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShowPage {
+    /// 所属画廊 ID
+    pub gid: i64,
+    /// 所属画廊令牌
+    pub token: String,
+    /// 当前页号，从 1 开始
+    pub page: i64,
+    /// 画廊的总页数
+    pub total: i64,
+    /// 图片地址
+    pub image_url: String,
+    /// 图片文件名
+    pub filename: String,
+    /// 图片宽度，单位为像素（px）
+    pub width: i64,
+    /// 图片高度，单位为像素（px）
+    pub height: i64,
+    /// 图片文件大小，如 `345.6 KiB`
+    pub size: String,
+    /// 重新加载图片的参数，见 [`ShowPageBuilder::nl`](crate::url::show::ShowPageBuilder::nl)
+    pub nl: Option<String>,
+    /// 原图信息，图片未经缩放或无法识别原图信息时为 None
+    pub original: Option<ShowPageOriginal>,
+    /// 用于 showpage API 请求的密钥
+    pub showkey: Option<String>,
+    /// 上一页链接
+    pub prev_href: Option<String>,
+    /// 下一页链接
+    pub next_href: Option<String>,
+}
+
+/// 原图信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShowPageOriginal {
+    /// 原图下载链接，需要登录
+    pub url: String,
+    /// 原图宽度，单位为像素（px）
+    pub width: i64,
+    /// 原图高度，单位为像素（px）
+    pub height: i64,
+    /// 原图文件大小，如 `1.23 MiB`
+    pub size: String,
+}
+
+impl ShowPage {
+    /// 解析图片页面
+    pub fn parse(html: String) -> EhResult<Self> {
+        let _span = tracing::debug_span!("eh_parse", kind = "page").entered();
+        let d = Html::parse_document(&html);
+        let (gid, token) = Self::parse_gallery(&d)?;
+        let (page, total) = Self::parse_page_number(&d)?;
+        let image_url = Self::parse_image_url(&d)?;
+        let (filename, width, height, size) = Self::parse_image_info(&d)?;
+        let nl = Self::parse_nl(&d)?;
+        let original = Self::parse_original(&d)?;
+        let showkey = regex(PATTERN_SHOWKEY)?
+            .captures(&html)
+            .map(|caps| caps["showkey"].to_string());
+        let prev_href = Self::parse_href(&d, "a#prev")?;
+        let next_href = Self::parse_href(&d, "a#next")?;
+        Ok(ShowPage {
+            gid,
+            token,
+            page,
+            total,
+            image_url,
+            filename,
+            width,
+            height,
+            size,
+            nl,
+            original,
+            showkey,
+            prev_href,
+            next_href,
+        })
+    }
+
+    /// 从返回画廊的链接解析画廊 ID 与令牌，不限制站点的域名
+    fn parse_gallery(d: &Html) -> EhResult<(i64, String)> {
+        let s = selector("div.sb > a")?;
+        let href = match d.select(&s).next().and_then(|a| a.attr("href")) {
+            Some(href) => href,
+            None => {
+                return Err(EhError::parse_selector(
+                    "gallery",
+                    "div.sb > a",
+                    "No gallery link.",
+                ))
+            }
+        };
+        let item = href
+            .parse::<GIDListItem>()
+            .map_err(|err| EhError::parse_selector("gallery", "div.sb > a", err.to_string()))?;
+        Ok((item.gid(), item.token().to_string()))
+    }
+
+    /// 解析当前页号与总页数
+    fn parse_page_number(d: &Html) -> EhResult<(i64, i64)> {
+        let s = selector("div.sn > div > span")?;
+        let spans: Vec<String> = d.select(&s).map(|s| text_content(s.text())).collect();
+        match spans.as_slice() {
+            [page, total, ..] => Ok((parse_to::<i64>(page)?, parse_to::<i64>(total)?)),
+            _ => Err(EhError::parse_selector(
+                "page number",
+                "div.sn > div > span",
+                "No page number.",
+            )),
+        }
+    }
+
+    /// 解析图片地址
+    fn parse_image_url(d: &Html) -> EhResult<String> {
+        let s = selector("img#img")?;
+        match d.select(&s).next().and_then(|img| img.attr("src")) {
+            Some(src) => Ok(src.to_string()),
+            None => Err(EhError::parse_selector("image", "img#img", "No image.")),
+        }
+    }
+
+    /// 解析图片的文件名、尺寸与文件大小
+    fn parse_image_info(d: &Html) -> EhResult<(String, i64, i64, String)> {
+        let r = regex(PATTERN_IMAGE_INFO)?;
+        let s = selector("div#i2 > div, div#i4 > div")?;
+        for div in d.select(&s) {
+            let text = text_content(div.text());
+            if let Some(caps) = r.captures(&text) {
+                return Ok((
+                    caps["name"].to_string(),
+                    parse_to::<i64>(&caps["width"])?,
+                    parse_to::<i64>(&caps["height"])?,
+                    caps["size"].to_string(),
+                ));
+            }
+        }
+        Err(EhError::parse_selector(
+            "image info",
+            "div#i2 > div, div#i4 > div",
+            "No image info.",
+        ))
+    }
+
+    /// 解析重新加载图片的参数
+    fn parse_nl(d: &Html) -> EhResult<Option<String>> {
+        let r = regex(PATTERN_NL)?;
+        let s = selector("a#loadfail")?;
+        let nl = d
+            .select(&s)
+            .next()
+            .and_then(|a| a.attr("onclick"))
+            .and_then(|onclick| r.captures(onclick))
+            .map(|caps| caps["nl"].to_string());
+        Ok(nl)
+    }
+
+    /// 解析原图信息，原图链接的文字无法识别时视为没有原图
+    fn parse_original(d: &Html) -> EhResult<Option<ShowPageOriginal>> {
+        let r = regex(PATTERN_ORIGINAL)?;
+        let s = selector(r#"div#i6 a[href*="/fullimg"], div#i7 a[href*="/fullimg"]"#)?;
+        let Some(a) = d.select(&s).next() else {
+            return Ok(None);
+        };
+        let url = a.attr("href").unwrap_or_default().to_string();
+        let text = text_content(a.text());
+        match r.captures(&text) {
+            Some(caps) => Ok(Some(ShowPageOriginal {
+                url,
+                width: parse_to::<i64>(&caps["width"])?,
+                height: parse_to::<i64>(&caps["height"])?,
+                size: caps["size"].to_string(),
+            })),
+            None => Ok(None),
+        }
+    }
+
+    /// 解析翻页链接
+    fn parse_href(d: &Html, s: &str) -> EhResult<Option<String>> {
+        let sel = selector(s)?;
+        let href = d
+            .select(&sel)
+            .next()
+            .and_then(|a| a.attr("href"))
+            .map(str::to_string);
+        Ok(href)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::EhError;
+
+    use super::ShowPage;
+
+    const SHOW_PAGE: &str = r##"<html><head><script type="text/javascript">
+var gid=2519745;
+var startpage=8;
+var showkey="0123456789a";
+</script></head><body>
+<div id="i1" class="sni" style="width:1280px"><h1>Title</h1>
+<div id="i2"><div class="sn"><a id="first" href="https://e-hentai.org/s/aaaaaaaaaa/2519745-1"></a><a id="prev" href="https://e-hentai.org/s/bbbbbbbbbb/2519745-7"></a><div><span>8</span> / <span>20</span></div><a id="next" href="https://e-hentai.org/s/cccccccccc/2519745-9"></a></div>
+<div>008.jpg :: 1280 x 1808 :: 345.6 KiB</div></div>
+<div id="i3"><a onclick="return load_image(9, 'cccccccccc')" href="https://e-hentai.org/s/cccccccccc/2519745-9"><img id="img" src="https://abc.hath.network/h/0123/keystamp=1/008.jpg" /></a></div>
+<div id="i5"><div class="sb"><a href="https://e-hentai.org/g/2519745/76939e430f/"><img src="https://ehgt.org/g/b.png" /></a></div></div>
+<div id="i6" class="if"><a href="https://e-hentai.org/?f_shash=abc">Show all galleries with this file</a>
+<a href="#" id="loadfail" onclick="return nl('12345-67890')">Reload broken image</a></div>
+<div id="i7" class="if"><img src="https://ehgt.org/g/mr.gif" class="mr" /> <a href="https://e-hentai.org/fullimg/2519745/8/xyz/008.jpg">Download original 2400 x 3390 1.23 MiB source</a></div>
+</div></body></html>"##;
+
+    #[test]
+    fn test_parse_show_page() {
+        let page = ShowPage::parse(SHOW_PAGE.to_string()).unwrap();
+        assert_eq!(page.gid, 2519745);
+        assert_eq!(page.token, "76939e430f");
+        assert_eq!((page.page, page.total), (8, 20));
+        assert_eq!(
+            page.image_url,
+            "https://abc.hath.network/h/0123/keystamp=1/008.jpg"
+        );
+        assert_eq!(page.filename, "008.jpg");
+        assert_eq!((page.width, page.height), (1280, 1808));
+        assert_eq!(page.size, "345.6 KiB");
+        assert_eq!(page.nl.as_deref(), Some("12345-67890"));
+        assert_eq!(page.showkey.as_deref(), Some("0123456789a"));
+        let original = page.original.unwrap();
+        assert_eq!(
+            original.url,
+            "https://e-hentai.org/fullimg/2519745/8/xyz/008.jpg"
+        );
+        assert_eq!((original.width, original.height), (2400, 3390));
+        assert_eq!(original.size, "1.23 MiB");
+        assert_eq!(
+            page.prev_href.as_deref(),
+            Some("https://e-hentai.org/s/bbbbbbbbbb/2519745-7")
+        );
+        assert_eq!(
+            page.next_href.as_deref(),
+            Some("https://e-hentai.org/s/cccccccccc/2519745-9")
+        );
+
+        let html = SHOW_PAGE.replace(r#"<div id="i7""#, r#"<div id="i8""#);
+        assert!(ShowPage::parse(html).unwrap().original.is_none());
+        let html = SHOW_PAGE.replace("Download original", "Original");
+        assert!(ShowPage::parse(html).unwrap().original.is_none());
+        let html = SHOW_PAGE.replace("https://e-hentai.org/g/", "https://eh.example.org/g/");
+        assert_eq!(ShowPage::parse(html).unwrap().gid, 2519745);
+        let html = SHOW_PAGE.replace(r#"id="img""#, r#"id="image""#);
+        assert!(matches!(ShowPage::parse(html), Err(EhError::Parse(_))));
+        let html = SHOW_PAGE.replace("/g/2519745/76939e430f/", "/s/76939e430f/2519745-1");
+        assert!(matches!(
+            ShowPage::parse(html),
+            Err(EhError::Parse(err)) if err.selector.as_deref() == Some("div.sb > a")
+        ));
+    }
+}
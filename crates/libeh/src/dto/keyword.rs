@@ -1,7 +1,9 @@
-use std::str::FromStr;
+use std::{fmt, str::FromStr};
 
 use serde::{Deserialize, Serialize};
 
+use crate::error::EhError;
+
 /// 搜索关键词
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Keyword {
@@ -35,9 +37,9 @@ pub enum Keyword {
     Uploader(String),
 }
 
-impl ToString for Keyword {
-    fn to_string(&self) -> String {
-        match self {
+impl fmt::Display for Keyword {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
             Keyword::Normal(keyword) => format!("\"{}\"", keyword),
             Keyword::Language(keyword) => format!("l:\"{}$\"", keyword),
             Keyword::Parody(keyword) => format!("p:\"{}$\"", keyword),
@@ -52,7 +54,8 @@ impl ToString for Keyword {
             Keyword::Reclass(keyword) => format!("r:\"{}$\"", keyword),
             Keyword::Temp(keyword) => format!("temp:\"{}$\"", keyword),
             Keyword::Uploader(keyword) => format!("uploader:\"{}$\"", keyword),
-        }
+        };
+        write!(f, "{}", s)
     }
 }
 
@@ -70,10 +73,10 @@ impl From<String> for Keyword {
 }
 
 impl FromStr for Keyword {
-    type Err = String;
+    type Err = EhError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let result: Vec<&str> = s.split(":").collect();
+        let result: Vec<&str> = s.split(':').collect();
         match result.len() {
             1 => Ok(Keyword::Normal(result[0].into())),
             2 => match result[0] {
@@ -90,9 +93,9 @@ impl FromStr for Keyword {
                 "reclass" | "r" => Ok(Keyword::Reclass(result[1].into())),
                 "temp" => Ok(Keyword::Temp(result[1].into())),
                 "uploader" => Ok(Keyword::Uploader(result[1].into())),
-                _ => Err(format!("Invalid keyword: {}", s)),
+                _ => Err(EhError::invalid_input(format!("Invalid keyword: {}", s))),
             },
-            _ => Err(format!("Invalid keyword: {}", s)),
+            _ => Err(EhError::invalid_input(format!("Invalid keyword: {}", s))),
         }
     }
 }
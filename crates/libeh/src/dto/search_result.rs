@@ -5,6 +5,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     dto::{gallery::category::Category, keyword::Keyword},
+    error::{EhError, EhResult},
     url::gallery::GalleryBuilder,
     utils::{
         regex::regex,
@@ -44,7 +45,8 @@ impl Default for SearchResult {
 }
 
 impl SearchResult {
-    pub fn parse(html: String) -> Result<Self, String> {
+    pub fn parse(html: String) -> EhResult<Self> {
+        let _span = tracing::debug_span!("eh_parse", kind = "search", bytes = html.len()).entered();
         let mut search_result = SearchResult::default();
         let d = Html::parse_document(&html);
 
@@ -53,45 +55,45 @@ impl SearchResult {
         let search_nav = if let Some(result) = result_search_nav.next() {
             result
         } else {
-            return Err(format!("Failed to parse search nav."));
+            return Err(EhError::parse_selector(
+                "search nav",
+                ".searchnav",
+                "No search nav.",
+            ));
         };
 
         let s = selector("#ufirst")?;
         if let Some(result) = search_nav.select(&s).next() {
-            search_result.first_href = match result.value().attr("href") {
-                Some(href) => Some(href.to_string()),
-                None => None,
-            };
+            search_result.first_href = result.value().attr("href").map(|href| href.to_string());
         }
 
         let s = selector("#uprev")?;
         if let Some(result) = search_nav.select(&s).next() {
-            search_result.prev_href = match result.value().attr("href") {
-                Some(href) => Some(href.to_string()),
-                None => None,
-            };
+            search_result.prev_href = result.value().attr("href").map(|href| href.to_string());
         }
 
         let s = selector("#unext")?;
         if let Some(result) = search_nav.select(&s).next() {
-            search_result.next_href = match result.value().attr("href") {
-                Some(href) => Some(href.to_string()),
-                None => None,
-            };
+            search_result.next_href = result.value().attr("href").map(|href| href.to_string());
         }
 
         let s = selector("#ulast")?;
         if let Some(result) = search_nav.select(&s).next() {
-            search_result.last_href = match result.value().attr("href") {
-                Some(href) => Some(href.to_string()),
-                None => None,
-            };
+            search_result.last_href = result.value().attr("href").map(|href| href.to_string());
         }
 
         let s = selector("table.itg")?;
         let table = match d.select(&s).next() {
             Some(table) => table,
-            None => return Err(format!("Failed to parse search result: {}", "No table.")),
+            // 没有结果或超出最后一页时页面只有提示，没有结果表格
+            None if Self::is_no_hits(&d)? => return Ok(search_result),
+            None => {
+                return Err(EhError::parse_selector(
+                    "search result",
+                    "table.itg",
+                    "No table.",
+                ))
+            }
         };
 
         // let mut list: Vec<GalleryInfo> = vec![];
@@ -99,22 +101,34 @@ impl SearchResult {
         for tr in table.select(&s) {
             match Self::parse_gallery_info(tr) {
                 Ok(gallery_info) => search_result.gallery_info_list.push(gallery_info),
-                Err(err) => println!("Failed to parse gallery info: {}", err),
+                Err(err) => tracing::warn!("Failed to parse gallery info: {}", err),
             }
         }
         Ok(search_result)
     }
 
-    fn parse_gallery_info(tr: ElementRef) -> Result<GalleryInfo, String> {
+    /// 页面是否为没有结果的提示页面
+    fn is_no_hits(d: &Html) -> EhResult<bool> {
+        let s = selector(".itg")?;
+        if d.select(&s).next().is_some() {
+            return Ok(false);
+        }
+        let s = selector("p")?;
+        Ok(d.select(&s)
+            .any(|p| text_content(p.text()).starts_with("No hits found")))
+    }
+
+    fn parse_gallery_info(tr: ElementRef) -> EhResult<GalleryInfo> {
         let mut gi = GalleryInfo::default();
         // 提取标题
         let s = selector(".glname")?;
         let glname = match tr.select(&s).next() {
             Some(element) => element,
             None => {
-                return Err(format!(
-                    "Failed to parse gallery title: {}",
-                    "No valid title."
+                return Err(EhError::parse_selector(
+                    "gallery title",
+                    ".glname",
+                    "No valid title.",
                 ))
             }
         };
@@ -122,14 +136,21 @@ impl SearchResult {
         let s = selector(".glink")?;
         let glink = match tr.select(&s).next() {
             Some(element) => element,
-            None => return Err(format!("")),
+            None => {
+                return Err(EhError::parse_selector(
+                    "gallery title",
+                    ".glink",
+                    "No title link.",
+                ))
+            }
         };
         let text = text_content(glink.text());
         gi.title = text;
         if gi.title.is_empty() {
-            return Err(format!(
-                "Failed to parse gallery title: {}",
-                "Title is empty."
+            return Err(EhError::parse_selector(
+                "gallery title",
+                ".glink",
+                "Title is empty.",
             ));
         }
 
@@ -139,20 +160,20 @@ impl SearchResult {
             Some(element) => element.value(),
             None => {
                 let Some(parent) = glname.parent() else {
-                    return Err(format!("Failed to parse gallery info: {}", "No link."));
+                    return Err(EhError::parse("gallery info", "No link."));
                 };
                 let parent = parent.value();
                 if !parent.is_element() {
-                    return Err(format!("Failed to parse gallery info: {}", "No link."));
+                    return Err(EhError::parse("gallery info", "No link."));
                 };
                 let parent = parent.as_element();
                 let Some(parent) = parent else {
-                    return Err(format!("Failed to parse gallery info: {}", "No link."));
+                    return Err(EhError::parse("gallery info", "No link."));
                 };
                 if parent.name() == "a" {
                     parent
                 } else {
-                    return Err(format!("Failed to parse gallery info: {}", "No link."));
+                    return Err(EhError::parse("gallery info", "No link."));
                 }
             }
         };
@@ -162,7 +183,7 @@ impl SearchResult {
             gi.gid = result.gid;
             gi.token = result.token;
         } else {
-            return Err(format!("Failed to parse gallery info: {}", "No link."));
+            return Err(EhError::parse("gallery info", "No link."));
         }
 
         // 提取 tags
@@ -179,7 +200,13 @@ impl SearchResult {
         let s = selector(".cn")?;
         let cn = match tr.select(&s).next() {
             Some(element) => element,
-            None => return Err(format!("Failed to parse gallery info: {}", "No category.")),
+            None => {
+                return Err(EhError::parse_selector(
+                    "gallery info",
+                    ".cn",
+                    "No category.",
+                ))
+            }
         };
         let text = text_content(cn.text());
         let category = Category::from(text);
@@ -191,9 +218,10 @@ impl SearchResult {
         let glthumb = match tr.select(&s).next() {
             Some(element) => element,
             None => {
-                return Err(format!(
-                    "Failed to parse gallery info: {}",
-                    "No thumb class element."
+                return Err(EhError::parse_selector(
+                    "gallery info",
+                    ".glthumb",
+                    "No thumb class element.",
                 ))
             }
         };
@@ -201,9 +229,10 @@ impl SearchResult {
         let glthumb_img = match glthumb.select(&s).next() {
             Some(element) => element,
             None => {
-                return Err(format!(
-                    "Failed to parse gallery info: {}",
-                    "No thumb img element."
+                return Err(EhError::parse_selector(
+                    "gallery info",
+                    "div:nth-child(1) > img",
+                    "No thumb img element.",
                 ))
             }
         };
@@ -212,7 +241,7 @@ impl SearchResult {
             Some(src) => src,
             None => match glthumb_img.attr("src") {
                 Some(src) => src,
-                None => return Err(format!("Failed to parse gallery info: {}", "No thumb src.")),
+                None => return Err(EhError::parse("gallery info", "No thumb src.")),
             },
         };
         gi.thumb = src.to_string();
@@ -221,7 +250,13 @@ impl SearchResult {
         let s = selector(".ir + div")?;
         let pages = match tr.select(&s).next() {
             Some(element) => element,
-            None => return Err(format!("Failed to parse gallery info: {}", "No pages.")),
+            None => {
+                return Err(EhError::parse_selector(
+                    "gallery info",
+                    ".ir + div",
+                    "No pages.",
+                ))
+            }
         };
         let text = text_content(pages.text());
         let r = regex(r"(?<page>\d+) pages?")?;
@@ -229,41 +264,39 @@ impl SearchResult {
             Some(caps) => {
                 let page = {
                     let Ok(page) = parse_to::<i64>(&caps["page"]) else {
-                        return Err(format!(
-                            "Failed to parse gallery info: {}",
-                            "Page parse error."
-                        ));
+                        return Err(EhError::parse("gallery info", "Page parse error."));
                     };
                     page
                 };
                 gi.pages = page;
             }
-            None => return Err(format!("Failed to parse gallery info: {}", "No page.")),
+            None => return Err(EhError::parse("gallery info", "No page.")),
         }
 
         // 提取上传时间与收藏信息
-        let s = selector(&String::from_iter(vec![
-            "#posted_",
-            gi.gid.to_string().as_str(),
-        ]))?;
+        let posted_selector = format!("#posted_{}", gi.gid);
+        let s = selector(&posted_selector)?;
         let posted = match tr.select(&s).next() {
             Some(element) => element,
-            None => return Err(format!("Failed to parse gallery info: {}", "No posted.")),
+            None => {
+                return Err(EhError::parse_selector(
+                    "gallery info",
+                    &posted_selector,
+                    "No posted.",
+                ))
+            }
         };
         let posted_text = text_content(posted.text());
         gi.posted = parse_posted(&posted_text)?;
         if let Some(style) = posted.attr("style") {
-            gi.favorite_slot = match parse_favorite_slot(style) {
-                Ok(slot) => slot,
-                Err(_) => -1,
-            };
+            gi.favorite_slot = parse_favorite_slot(style).unwrap_or(-1);
         }
 
         // 提取评分
         let s = selector(".ir")?;
         let ir = match tr.select(&s).next() {
             Some(element) => element,
-            None => return Err(format!("Failed to parse gallery info: {}", "No ir.")),
+            None => return Err(EhError::parse_selector("gallery info", ".ir", "No ir.")),
         };
         if let Some(style) = ir.attr("style") {
             gi.rating = parse_rating(style)?;
@@ -273,7 +306,13 @@ impl SearchResult {
         let s = selector(".glhide > div:nth-child(1)")?;
         let glhide = match tr.select(&s).next() {
             Some(element) => element,
-            None => return Err(format!("Failed to parse gallery info: {}", "No glhide.")),
+            None => {
+                return Err(EhError::parse_selector(
+                    "gallery info",
+                    ".glhide > div:nth-child(1)",
+                    "No glhide.",
+                ))
+            }
         };
         let uploader = text_content(glhide.text());
         if uploader.ne("(Disowned)") {
@@ -289,7 +328,23 @@ mod tests {
     use std::fs::File;
     use std::io::Read;
 
-    use crate::dto::search_result::SearchResult;
+    use crate::{dto::search_result::SearchResult, error::EhError};
+
+    #[test]
+    fn test_parse_no_hits() {
+        let html = r#"<html><body><div class="searchnav"></div><div class="ido"><p>No hits found</p></div></body></html>"#;
+        let result = SearchResult::parse(html.to_string()).unwrap();
+        assert!(result.gallery_info_list.is_empty());
+        assert_eq!(result.next_href, None);
+
+        // 缩略图模式等其他布局不是没有结果
+        let html =
+            r#"<html><body><div class="searchnav"></div><div class="itg gld"></div></body></html>"#;
+        let err = SearchResult::parse(html.to_string()).unwrap_err();
+        assert!(matches!(err, EhError::Parse(err) if err.selector.as_deref() == Some("table.itg")));
+        let html = r#"<html><body><div class="searchnav"></div></body></html>"#;
+        assert!(SearchResult::parse(html.to_string()).is_err());
+    }
 
     #[test]
     fn test_parse_search_result() {
@@ -1,6 +1,13 @@
 use reqwest::Url;
 use serde::{Deserialize, Serialize};
 
+use crate::error::{EhError, EhResult};
+
+/// E-Hentai 的默认地址
+pub(crate) const EH_URL: &str = "https://e-hentai.org/";
+/// ExHentai 的默认地址
+pub(crate) const EX_URL: &str = "https://exhentai.org/";
+
 /// 站点类型
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum Site {
@@ -28,13 +35,24 @@ impl From<String> for Site {
     }
 }
 
-impl From<Site> for Url {
-    /// 将站点类型转换为 Url
-    fn from(value: Site) -> Self {
-        match value {
-            Site::Eh => Url::parse("https://e-hentai.org/").unwrap(),
-            Site::Ex => Url::parse("https://exhentai.org/").unwrap(),
-            _ => panic!("Unrecognized site."),
+impl Site {
+    /// 站点的默认地址，未知站点返回 None
+    pub fn default_url(&self) -> Option<Url> {
+        match self {
+            Site::Eh => Url::parse(EH_URL).ok(),
+            Site::Ex => Url::parse(EX_URL).ok(),
+            Site::Un => None,
         }
     }
 }
+
+impl TryFrom<Site> for Url {
+    type Error = EhError;
+
+    /// 将站点类型转换为默认地址，未知站点返回错误
+    fn try_from(value: Site) -> EhResult<Self> {
+        value
+            .default_url()
+            .ok_or_else(|| EhError::invalid_input("Unrecognized site."))
+    }
+}
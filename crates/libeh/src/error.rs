@@ -0,0 +1,271 @@
+use std::{convert::Infallible, error::Error, fmt, io, time::Duration};
+
+/// libeh 中所有可失败操作的返回类型
+pub type EhResult<T> = Result<T, EhError>;
+
+/// libeh 的错误类型
+#[derive(Debug)]
+pub enum EhError {
+    /// 网络传输错误，如连接失败、超时、读取响应失败等
+    Transport(reqwest::Error),
+    /// 服务器返回了非成功的 HTTP 状态码
+    HttpStatus {
+        /// HTTP 状态码
+        status: u16,
+        /// 请求的 URL
+        url: String,
+    },
+    /// 页面或数据解析失败
+    Parse(ParseError),
+    /// 无效的输入，如无法识别的 URL、关键词等
+    InvalidInput(String),
+    /// 站点返回的特定状况，如画廊已被删除、内容警告等
+    Site(SiteError),
+    /// 登录或身份验证失败
+    Auth(AuthError),
+    /// 本地文件读写错误
+    Io(io::Error),
+    /// 离线模式下缓存中没有该请求的响应，附带请求的 URL
+    CacheMiss(String),
+    /// 操作已通过取消令牌取消
+    Cancelled,
+    /// 代理池中的所有代理都已被移出，附带最早结束冷却的代理的剩余冷却时间
+    ProxyPoolExhausted {
+        /// 最早结束冷却的代理的剩余冷却时间
+        cooldown_remaining: Option<Duration>,
+    },
+}
+
+/// 解析错误及其上下文
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    /// 正在解析的字段
+    pub field: String,
+    /// 定位该字段时使用的 CSS 选择器
+    pub selector: Option<String>,
+    /// 错误描述
+    pub message: String,
+}
+
+/// 站点返回的特定状况
+#[derive(Debug, Clone, PartialEq)]
+pub enum SiteError {
+    /// 画廊包含冒犯性内容，需要确认后才能浏览
+    Offensive,
+    /// 画廊已被删除（This gallery is pining for the fjords.）
+    Pining,
+    /// 站点返回的错误提示
+    Message(String),
+    /// IP 被临时封禁，附带站点给出的剩余封禁时长
+    IpBanned {
+        /// 剩余的封禁时长，无法解析时为 None
+        expires_in: Option<Duration>,
+    },
+    /// 图片浏览配额已用尽
+    QuotaExceeded,
+    /// ExHentai 返回了空白页面（Sad Panda），通常意味着没有访问权限
+    SadPanda,
+}
+
+/// 登录或身份验证失败的原因
+#[derive(Debug, Clone, PartialEq)]
+pub enum AuthError {
+    /// 用户名或密码错误
+    InvalidCredentials,
+    /// 站点要求完成验证码
+    CaptchaRequired,
+    /// 账号没有 ExHentai 访问权限
+    ExHentaiDenied,
+    /// 站点拒绝登录，附带站点给出的提示
+    Rejected(String),
+    /// 当前会话未登录
+    NotLoggedIn,
+}
+
+impl EhError {
+    /// 创建一个解析错误
+    pub(crate) fn parse(field: &str, message: impl Into<String>) -> Self {
+        EhError::Parse(ParseError {
+            field: field.to_string(),
+            selector: None,
+            message: message.into(),
+        })
+    }
+
+    /// 创建一个带有选择器上下文的解析错误
+    pub(crate) fn parse_selector(field: &str, selector: &str, message: impl Into<String>) -> Self {
+        EhError::Parse(ParseError {
+            field: field.to_string(),
+            selector: Some(selector.to_string()),
+            message: message.into(),
+        })
+    }
+
+    /// 创建一个无效输入错误
+    pub(crate) fn invalid_input(message: impl Into<String>) -> Self {
+        EhError::InvalidInput(message.into())
+    }
+
+    /// 是否为站点对访问的限制（IP 封禁、配额耗尽、Sad Panda 或代理池耗尽），此类错误不应立即重试
+    pub fn is_restricted(&self) -> bool {
+        matches!(
+            self,
+            EhError::Site(SiteError::IpBanned { .. })
+                | EhError::ProxyPoolExhausted { .. }
+                | EhError::Site(SiteError::QuotaExceeded)
+                | EhError::Site(SiteError::SadPanda)
+        )
+    }
+
+    /// 若为 IP 封禁，返回站点给出的剩余封禁时长
+    pub fn ban_expires_in(&self) -> Option<Duration> {
+        match self {
+            EhError::Site(SiteError::IpBanned { expires_in }) => *expires_in,
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for EhError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EhError::Transport(err) => write!(f, "Transport error: {}", err),
+            EhError::HttpStatus { status, url } => {
+                write!(f, "Unexpected HTTP status {} from {}", status, url)
+            }
+            EhError::Parse(err) => write!(f, "{}", err),
+            EhError::InvalidInput(message) => write!(f, "Invalid input: {}", message),
+            EhError::Site(err) => write!(f, "{}", err),
+            EhError::Auth(err) => write!(f, "{}", err),
+            EhError::Io(err) => write!(f, "IO error: {}", err),
+            EhError::CacheMiss(url) => write!(f, "No cached response for {} in offline mode", url),
+            EhError::Cancelled => write!(f, "Operation cancelled."),
+            EhError::ProxyPoolExhausted {
+                cooldown_remaining: Some(remaining),
+            } => write!(
+                f,
+                "All proxies in the pool are ejected, cooling down for {} seconds.",
+                remaining.as_secs()
+            ),
+            EhError::ProxyPoolExhausted {
+                cooldown_remaining: None,
+            } => {
+                write!(f, "All proxies in the pool are ejected.")
+            }
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Failed to parse {}: {}", self.field, self.message)?;
+        if let Some(selector) = &self.selector {
+            write!(f, " (selector: {})", selector)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for SiteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SiteError::Offensive => write!(f, "Gallery contains offensive content."),
+            SiteError::Pining => write!(f, "Gallery has been removed."),
+            SiteError::Message(message) => write!(f, "Site error: {}", message),
+            SiteError::IpBanned {
+                expires_in: Some(expires_in),
+            } => write!(
+                f,
+                "IP address is temporarily banned, expires in {} seconds.",
+                expires_in.as_secs()
+            ),
+            SiteError::IpBanned { expires_in: None } => {
+                write!(f, "IP address is temporarily banned.")
+            }
+            SiteError::QuotaExceeded => write!(f, "Image viewing quota exceeded."),
+            SiteError::SadPanda => write!(f, "ExHentai returned an empty page (sad panda)."),
+        }
+    }
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthError::InvalidCredentials => write!(f, "Username or password incorrect."),
+            AuthError::CaptchaRequired => write!(f, "Login requires completing a captcha."),
+            AuthError::ExHentaiDenied => write!(f, "Account has no access to ExHentai."),
+            AuthError::Rejected(message) => write!(f, "Login rejected: {}", message),
+            AuthError::NotLoggedIn => write!(f, "Session is not logged in."),
+        }
+    }
+}
+
+impl Error for EhError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            EhError::Transport(err) => Some(err),
+            EhError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+impl Error for SiteError {}
+
+impl Error for AuthError {}
+
+impl From<reqwest::Error> for EhError {
+    fn from(err: reqwest::Error) -> Self {
+        EhError::Transport(err)
+    }
+}
+
+impl From<Infallible> for EhError {
+    fn from(err: Infallible) -> Self {
+        match err {}
+    }
+}
+
+impl From<io::Error> for EhError {
+    fn from(err: io::Error) -> Self {
+        EhError::Io(err)
+    }
+}
+
+impl From<ParseError> for EhError {
+    fn from(err: ParseError) -> Self {
+        EhError::Parse(err)
+    }
+}
+
+impl From<SiteError> for EhError {
+    fn from(err: SiteError) -> Self {
+        EhError::Site(err)
+    }
+}
+
+impl From<AuthError> for EhError {
+    fn from(err: AuthError) -> Self {
+        EhError::Auth(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EhError, SiteError};
+
+    #[test]
+    fn test_error_display() {
+        let err = EhError::parse_selector("gallery title", ".glink", "Title is empty.");
+        assert_eq!(
+            err.to_string(),
+            "Failed to parse gallery title: Title is empty. (selector: .glink)"
+        );
+        let err = EhError::invalid_input("Invalid keyword: a:b:c");
+        assert_eq!(err.to_string(), "Invalid input: Invalid keyword: a:b:c");
+        let err = EhError::from(SiteError::Pining);
+        assert!(matches!(err, EhError::Site(SiteError::Pining)));
+    }
+}
@@ -6,6 +6,8 @@
 pub mod client;
 /// 数据传输对象
 pub mod dto;
+/// 错误类型
+pub mod error;
 /// 为 [EhTagTranslation/DatabaseReleases](https://github.com/EhTagTranslation/DatabaseReleases) 设计的解析器，用于解析标签翻译
 pub mod tags;
 /// 对 e-hentai/exhentai 的链接构筑工具与解析工具
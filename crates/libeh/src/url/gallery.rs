@@ -1,12 +1,18 @@
 use reqwest::Url;
 
-use crate::{dto::site::Site, utils::regex::regex};
+use crate::{
+    dto::site::{EH_URL, EX_URL},
+    error::{EhError, EhResult},
+    utils::regex::regex,
+};
 
 #[derive(Debug, Clone)]
 pub struct GalleryBuilder {
     pub gid: i64,
     pub token: String,
     pub p: i64,
+    /// 是否显示全部评论（`hc=1`），默认只显示部分评论
+    pub all_comments: bool,
 }
 
 impl GalleryBuilder {
@@ -15,6 +21,7 @@ impl GalleryBuilder {
             gid,
             token: token.to_string(),
             p: 0,
+            all_comments: false,
         }
     }
 
@@ -22,53 +29,77 @@ impl GalleryBuilder {
         self.p = p;
         self
     }
+
+    /// 设置是否显示全部评论
+    pub fn all_comments(&mut self, all_comments: bool) -> &mut Self {
+        self.all_comments = all_comments;
+        self
+    }
 }
 
 impl GalleryBuilder {
-    pub fn parse(s: String) -> Result<Self, String> {
+    pub fn parse(s: String) -> EhResult<Self> {
         let p = regex(
             r"https?://(?<site>e-hentai.org|exhentai.org)/(?:g|mpv)/(?<gid>\d+)/(?<token>[0-9a-f]{10})",
         )?;
         let Some(caps) = p.captures(&s) else {
-            return Err(format!("Failed to parse gallery url: {}", s));
+            return Err(EhError::invalid_input(format!(
+                "Invalid gallery url: {}",
+                s
+            )));
         };
         Ok(Self {
             gid: {
                 let Ok(gid) = caps["gid"].parse::<i64>() else {
-                    return Err(format!("Failed to parse gallery gid: {}", s));
+                    return Err(EhError::invalid_input(format!(
+                        "Invalid gallery gid: {}",
+                        s
+                    )));
                 };
                 gid
             },
             token: caps["token"].to_string(),
             p: 0,
+            all_comments: false,
         })
     }
 
-    pub fn ex_url(&self) -> Url {
-        let mut url: Url = Site::Ex.into();
-        url.set_path(&format!("g/{}/{}/", self.gid, self.token));
+    /// 以指定的站点地址生成画廊 URL
+    pub fn url(&self, base: &Url) -> Url {
+        let mut url = base
+            .join(&format!("g/{}/{}/", self.gid, self.token))
+            .unwrap_or_else(|_| base.clone());
         if self.p > 0 {
-            url.set_query(Some(&format!("p={}", self.p)));
+            url.query_pairs_mut().append_pair("p", &self.p.to_string());
+        }
+        if self.all_comments {
+            url.query_pairs_mut().append_pair("hc", "1");
         }
         url
     }
 
+    pub fn ex_url(&self) -> Url {
+        self.url(&Url::parse(EX_URL).unwrap())
+    }
+
     pub fn eh_url(&self) -> Url {
-        let mut url: Url = Site::Eh.into();
-        url.set_path(&format!("g/{}/{}/", self.gid, self.token));
-        if self.p > 0 {
-            url.set_query(Some(&format!("p={}", self.p)));
-        }
-        url
+        self.url(&Url::parse(EH_URL).unwrap())
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
+
     use tokio::{fs::File, io::AsyncWriteExt};
 
     use crate::{
-        client::{client::EhClient, config::EhClientConfig, proxy::EhClientProxy},
+        client::{
+            client::EhClient,
+            config::EhClientConfig,
+            proxy::EhClientProxy,
+            transport::{EhResponse, FixtureTransport},
+        },
         dto::site::Site,
         url::gallery::GalleryBuilder,
     };
@@ -78,10 +109,22 @@ mod tests {
         let url = GalleryBuilder::parse("https://e-hentai.org/g/2519745/76939e430f/".into())?;
         assert_eq!(url.gid, 2519745);
         assert_eq!(url.token, "76939e430f");
+        let base = reqwest::Url::parse("https://exhentai.org/")?;
+        let mut builder = GalleryBuilder::new(2519745, "76939e430f");
+        assert_eq!(
+            builder.url(&base).as_str(),
+            "https://exhentai.org/g/2519745/76939e430f/"
+        );
+        builder.page(2).all_comments(true);
+        assert_eq!(
+            builder.url(&base).as_str(),
+            "https://exhentai.org/g/2519745/76939e430f/?p=2&hc=1"
+        );
         Ok(())
     }
 
     #[tokio::test]
+    #[ignore = "需要通过本地代理访问站点"]
     async fn test_gallery_builder_request() -> Result<(), Box<dyn std::error::Error>> {
         let gallery_builder = GalleryBuilder::new(2791585, "3e7e1c7107");
         let proxy = EhClientProxy::new("http", "127.0.0.1", 7897);
@@ -89,6 +132,7 @@ mod tests {
             site: Site::Eh,
             proxy: Some(proxy),
             auth: None,
+            ..Default::default()
         };
         let client = EhClient::new(config);
         let text = client.get_html(gallery_builder.eh_url()).await?;
@@ -104,4 +148,21 @@ mod tests {
         assert!(result.is_ok());
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_gallery_builder_fixture() -> Result<(), Box<dyn std::error::Error>> {
+        let gallery_builder = GalleryBuilder::new(2791585, "3e7e1c7107");
+        let transport = Arc::new(FixtureTransport::new().get(
+            gallery_builder.eh_url().as_str(),
+            EhResponse::new(200, "<html>gallery</html>"),
+        ));
+        let client = EhClient::new_with_transport(EhClientConfig::default(), transport.clone());
+        let text = client.get_html(gallery_builder.eh_url()).await?;
+        assert_eq!(text, "<html>gallery</html>");
+        assert_eq!(
+            transport.requests()[0].url,
+            "https://e-hentai.org/g/2791585/3e7e1c7107/"
+        );
+        Ok(())
+    }
 }
@@ -1,4 +1,5 @@
 pub mod gallery;
 pub mod search;
+pub mod show;
 #[cfg(test)]
 pub mod test;
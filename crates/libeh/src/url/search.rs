@@ -1,5 +1,6 @@
-use crate::dto::{
-    gallery::category::Category, keyword::Keyword, search_offset::Offset, site::Site,
+use crate::{
+    dto::{gallery::category::Category, keyword::Keyword, search_offset::Offset, site::Site},
+    error::{EhError, EhResult},
 };
 use reqwest::Url;
 use serde::{Deserialize, Serialize};
@@ -28,6 +29,7 @@ pub struct SearchOptions {
 #[derive(Debug, Clone)]
 pub struct SearchBuilder {
     _site: Site,
+    _base: Option<Url>,
     _watched: bool,
     _offset: Option<Offset>,
     _category: u16,
@@ -51,6 +53,7 @@ impl SearchBuilder {
         // 设置disable_filters_for_tags为false
         Self {
             _site: site,
+            _base: None,
             _watched: false,
             _offset: None,
             _category: 0,
@@ -99,7 +102,7 @@ impl SearchBuilder {
         let disabled = (self._category & u16::from(category)) == u16::from(category);
         // 如果已禁用，则去除该类别的标志位
         if disabled {
-            self._category = self._category & (1023 ^ u16::from(category));
+            self._category &= 1023 ^ u16::from(category);
         } else {
             // 如果未禁用，则设置该类别的标志位
             self._category |= u16::from(category);
@@ -151,7 +154,7 @@ impl SearchBuilder {
 
     /// 设置画廊的最低评分
     pub fn rating(mut self, rating: i8) -> SearchBuilder {
-        if rating >= 0 && rating <= 5 {
+        if (0..=5).contains(&rating) {
             self._advsearch.rating = rating;
         }
         self
@@ -175,22 +178,48 @@ impl SearchBuilder {
         self
     }
 
+    /// 使用指定的站点地址代替站点类型的默认地址，如镜像或本地测试服务器
+    pub fn base_url(mut self, url: Url) -> SearchBuilder {
+        self._base = Some(url);
+        self
+    }
+
     /// 获取当前类别
     pub fn category(&self) -> u16 {
         self._category
     }
 
+    /// 获取站点类型
+    pub fn site(&self) -> Site {
+        self._site
+    }
+
+    /// 是否设置了站点地址
+    pub fn has_base_url(&self) -> bool {
+        self._base.is_some()
+    }
+
+    /// 获取当前偏移量
+    pub fn current_offset(&self) -> Option<&Offset> {
+        self._offset.as_ref()
+    }
+
     /// 获取基础URL
-    fn build_base_url(&self) -> Result<Url, String> {
-        let mut url = Url::from(self._site);
+    fn build_base_url(&self) -> EhResult<Url> {
+        let mut url = match &self._base {
+            Some(base) => base.clone(),
+            None => Url::try_from(self._site)?,
+        };
         if self._watched {
-            url.set_path("/watched");
+            url = url
+                .join("watched")
+                .map_err(|err| EhError::invalid_input(err.to_string()))?;
         }
         Ok(url)
     }
 
     /// 向URL中追加分类信息
-    fn build_append_category(&self, mut url: Url) -> Result<Url, String> {
+    fn build_append_category(&self, mut url: Url) -> EhResult<Url> {
         if self._category != 0 {
             let mut query_pairs = url.query_pairs_mut();
             query_pairs.append_pair("f_cats", &self._category.to_string());
@@ -199,7 +228,7 @@ impl SearchBuilder {
     }
 
     /// 在给定的URL后面追加偏移量
-    fn build_append_offset(&self, mut url: Url) -> Result<Url, String> {
+    fn build_append_offset(&self, mut url: Url) -> EhResult<Url> {
         // 如果_offset存在
         if let Some(offset) = self._offset.clone() {
             let mut query_pairs = url.query_pairs_mut();
@@ -209,7 +238,7 @@ impl SearchBuilder {
     }
 
     /// 向URL中追加关键词
-    fn build_append_keywors(&self, mut url: Url) -> Result<Url, String> {
+    fn build_append_keywors(&self, mut url: Url) -> EhResult<Url> {
         // 创建一个空的关键词列表
         let mut keyword_list: Vec<String> = vec![];
         // 遍历关键词列表
@@ -226,7 +255,7 @@ impl SearchBuilder {
         Ok(url)
     }
 
-    fn build_append_advanced_search(&self, mut url: Url) -> Result<Url, String> {
+    fn build_append_advanced_search(&self, mut url: Url) -> EhResult<Url> {
         // 如果高级搜索功能已启用
         if self._advsearch.enabled {
             // 获取可修改的查询参数
@@ -279,7 +308,7 @@ impl SearchBuilder {
         Ok(url)
     }
 
-    pub fn build(self) -> Result<Url, String> {
+    pub fn build(self) -> EhResult<Url> {
         let mut url = self.build_base_url()?;
         url = self.build_append_offset(url)?;
         url = self.build_append_category(url)?;
@@ -312,6 +341,6 @@ mod tests {
             .enable_advanced_search()
             .add_keyword(Keyword::Female("living clothes".to_string()));
         let url = builder.build().unwrap();
-        println!("url: {}", url.to_string());
+        println!("url: {}", url);
     }
 }
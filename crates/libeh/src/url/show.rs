@@ -0,0 +1,99 @@
+use reqwest::Url;
+
+use crate::{
+    dto::{
+        api::PageListItem,
+        site::{EH_URL, EX_URL},
+    },
+    error::EhResult,
+};
+
+/// 图片页面（`/s/{page_token}/{gid}-{page}`）的 URL 构造器
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShowPageBuilder {
+    pub gid: i64,
+    pub page_token: String,
+    /// 页号，从 1 开始
+    pub page: i32,
+    /// 重新加载图片的参数（`nl`），用于换一个图片服务器
+    pub nl: Option<String>,
+}
+
+impl ShowPageBuilder {
+    pub fn new(gid: i64, page_token: &str, page: i32) -> Self {
+        Self {
+            gid,
+            page_token: page_token.to_string(),
+            page,
+            nl: None,
+        }
+    }
+
+    /// 设置重新加载图片的参数
+    pub fn nl(&mut self, nl: &str) -> &mut Self {
+        self.nl = Some(nl.to_string());
+        self
+    }
+
+    /// 从图片页面 URL 解析，不限制站点的域名
+    pub fn parse(s: &str) -> EhResult<Self> {
+        let item: PageListItem = s.parse()?;
+        Ok(item.into())
+    }
+
+    /// 以指定的站点地址生成图片页面 URL
+    pub fn url(&self, base: &Url) -> Url {
+        let mut url = base
+            .join(&format!("s/{}/{}-{}", self.page_token, self.gid, self.page))
+            .unwrap_or_else(|_| base.clone());
+        if let Some(nl) = &self.nl {
+            url.query_pairs_mut().append_pair("nl", nl);
+        }
+        url
+    }
+
+    pub fn ex_url(&self) -> Url {
+        self.url(&Url::parse(EX_URL).unwrap())
+    }
+
+    pub fn eh_url(&self) -> Url {
+        self.url(&Url::parse(EH_URL).unwrap())
+    }
+}
+
+impl From<PageListItem> for ShowPageBuilder {
+    fn from(item: PageListItem) -> Self {
+        Self::new(item.gid(), item.page_token(), item.page())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::Url;
+
+    use crate::error::EhError;
+
+    use super::ShowPageBuilder;
+
+    #[test]
+    fn test_show_page_builder() -> Result<(), Box<dyn std::error::Error>> {
+        let builder = ShowPageBuilder::parse("https://e-hentai.org/s/d384d63ec0/2519745-8")?;
+        assert_eq!(builder, ShowPageBuilder::new(2519745, "d384d63ec0", 8));
+        assert_eq!(
+            builder.ex_url().as_str(),
+            "https://exhentai.org/s/d384d63ec0/2519745-8"
+        );
+        let mut builder = builder;
+        builder.nl("12345-67890");
+        let base = Url::parse("https://e-hentai.org/")?;
+        assert_eq!(
+            builder.url(&base).as_str(),
+            "https://e-hentai.org/s/d384d63ec0/2519745-8?nl=12345-67890"
+        );
+        assert!(matches!(
+            ShowPageBuilder::parse("https://e-hentai.org/g/2519745/76939e430f/"),
+            Err(EhError::InvalidInput(_))
+        ));
+        Ok(())
+    }
+}
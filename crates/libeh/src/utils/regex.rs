@@ -1,9 +1,11 @@
 use regex::Regex;
 
+use crate::error::{EhError, EhResult};
+
 /// 生成正则表达式对象
-pub fn regex(regex: &str) -> Result<Regex, String> {
+pub fn regex(regex: &str) -> EhResult<Regex> {
     match Regex::new(regex) {
         Ok(regex) => Ok(regex),
-        Err(err) => Err(format!("Failed to parse regex: {}", err)),
+        Err(err) => Err(EhError::parse("regex", err.to_string())),
     }
 }
@@ -4,12 +4,17 @@ use chrono::{DateTime, NaiveDateTime, Utc};
 use scraper::{element_ref::Text, Selector};
 
 use super::regex::regex;
+use crate::error::{EhError, EhResult};
 
 /// 根据给定的选择器字符串创建一个选择器对象
-pub fn selector(selector: &str) -> Result<Selector, String> {
+pub fn selector(selector: &str) -> EhResult<Selector> {
     match Selector::parse(selector) {
-        Ok(selector) => Ok(selector),
-        Err(err) => Err(format!("Failed to parse selector: {}", err)),
+        Ok(s) => Ok(s),
+        Err(err) => Err(EhError::parse_selector(
+            "selector",
+            selector,
+            err.to_string(),
+        )),
     }
 }
 
@@ -24,13 +29,13 @@ pub fn text_content(text: Text) -> String {
 }
 
 /// 解析画廊的发布时间
-pub fn parse_posted(text: &str) -> Result<DateTime<Utc>, String> {
+pub fn parse_posted(text: &str) -> EhResult<DateTime<Utc>> {
     // 尝试从字符串中解析出一个日期时间
     match NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M") {
         // 如果解析成功，则将日期时间转换为UTC时间并返回
         Ok(date) => Ok(date.and_utc()),
         // 如果解析失败，则返回解析错误信息
-        Err(err) => Err(format!("Failed to parse posted: {}", err)),
+        Err(err) => Err(EhError::parse("posted", err.to_string())),
     }
 }
 
@@ -38,7 +43,7 @@ pub fn parse_posted(text: &str) -> Result<DateTime<Utc>, String> {
 struct FavoriteSlotRgba(i16, i16, i16);
 
 /// 解析样式颜色
-fn parse_style_color(text: &str) -> Result<FavoriteSlotRgba, String> {
+fn parse_style_color(text: &str) -> EhResult<FavoriteSlotRgba> {
     // 匹配rgba颜色值的正则表达式
     let r = regex(r"background-color:rgba\((\d+),(\d+),(\d+),")?;
     // 匹配到颜色值
@@ -55,16 +60,16 @@ fn parse_style_color(text: &str) -> Result<FavoriteSlotRgba, String> {
             match (r, g, b) {
                 (Ok(r), Ok(g), Ok(b)) => Ok(FavoriteSlotRgba(r, g, b)),
                 // 解析失败
-                _ => Err(format!("Failed to parse style color: {}", "No color.")),
+                _ => Err(EhError::parse("style color", "No color.")),
             }
         }
         // 匹配失败
-        None => Err(format!("Failed to parse style color: {}", "No color.")),
+        None => Err(EhError::parse("style color", "No color.")),
     }
 }
 
 /// 解析收藏夹槽位
-pub fn parse_favorite_slot(text: &str) -> Result<isize, String> {
+pub fn parse_favorite_slot(text: &str) -> EhResult<isize> {
     // 解析颜色
     let rgb = parse_style_color(text)?;
 
@@ -82,17 +87,14 @@ pub fn parse_favorite_slot(text: &str) -> Result<isize, String> {
         FavoriteSlotRgba(224, 128, 224) => 9,
         _ => {
             // 未知槽位
-            return Err(format!(
-                "Failed to parse favorite slot: {}",
-                "Unknown slot."
-            ));
+            return Err(EhError::parse("favorite slot", "Unknown slot."));
         }
     };
     // 返回槽位
     Ok(slot)
 }
 
-pub fn parse_rating(text: &str) -> Result<f32, String> {
+pub fn parse_rating(text: &str) -> EhResult<f32> {
     // 使用正则表达式匹配文本中的数字
     let r = regex(r"-?(\d+)px -?(\d+)px")?;
     // 匹配成功后，获取匹配到的数字
@@ -101,9 +103,9 @@ pub fn parse_rating(text: &str) -> Result<f32, String> {
             // 初始化评分值为5.0
             let rating: f32 = 5.0;
             // 解析第一个数字为i32类型
-            let major = caps[1].parse::<i32>().unwrap();
+            let major = parse_to::<i32>(&caps[1])?;
             // 解析第二个数字为i32类型
-            let patch = caps[2].parse::<i32>().unwrap();
+            let patch = parse_to::<i32>(&caps[2])?;
             // 计算主要评分值
             let rating = rating - major as f32 / 16.0;
             // 如果补丁号为21，则减去0.5
@@ -114,12 +116,12 @@ pub fn parse_rating(text: &str) -> Result<f32, String> {
                 Ok(rating)
             }
         }
-        None => Err(format!("Failed to parse rating: {}", "No rating.")),
+        None => Err(EhError::parse("rating", "No rating.")),
     }
 }
 
 /// 转换字符串为指定类型，类型需要实现 FromStr trait
-pub fn parse_to<T: FromStr>(value: &str) -> Result<T, String> {
+pub fn parse_to<T: FromStr>(value: &str) -> EhResult<T> {
     // 尝试将字符串解析为指定类型
     let result = value.parse::<T>();
     // 根据解析结果进行处理
@@ -127,6 +129,9 @@ pub fn parse_to<T: FromStr>(value: &str) -> Result<T, String> {
         // 解析成功，返回解析结果
         Ok(value) => Ok(value),
         // 解析失败，返回错误信息
-        Err(_) => Err(format!("Failed to parse to number: {}", value)),
+        Err(_) => Err(EhError::parse(
+            "number",
+            format!("Invalid value: {}", value),
+        )),
     }
 }